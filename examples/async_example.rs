@@ -1,7 +1,9 @@
 use dbus::nonblock::NonblockReply;
-use dbus_udisks2::{AsyncUDisks2, Disks};
+use dbus_udisks2::options::Wakeup;
+use dbus_udisks2::AsyncUDisks2;
 use std::env::args;
 use std::ops::Deref;
+use std::path::Path;
 
 #[tokio::main]
 async fn main() {
@@ -23,10 +25,9 @@ async fn main() {
 
 async fn print<T: NonblockReply, C: Deref<Target = T>>(conn: C) {
     let udisks2 = AsyncUDisks2::new(conn).await.unwrap();
-    let disks = Disks::new_async(&udisks2);
-    for device in disks.devices {
-        println!("{:#?}", device);
-        if let Ok(smart_data) = udisks2.smart_attributes(&device.drive, false).await {
+    for device in udisks2.full_snapshot(Wakeup::Prevent).await.devices {
+        println!("{:#?}", device.device);
+        if let Some(smart_data) = device.smart {
             println!("{:#?}", smart_data);
         }
     }
@@ -34,9 +35,7 @@ async fn print<T: NonblockReply, C: Deref<Target = T>>(conn: C) {
 
 async fn print_block<T: NonblockReply, C: Deref<Target = T>>(conn: C, block_name: &str) {
     let udisks2 = AsyncUDisks2::new(conn).await.unwrap();
-    for block in udisks2.get_blocks() {
-        if block.device.to_str().unwrap() == block_name {
-            println!("{:#?}", block);
-        }
+    if let Some(block) = udisks2.get_block_by_device(Path::new(block_name)) {
+        println!("{:#?}", block);
     }
 }
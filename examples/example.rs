@@ -1,5 +1,7 @@
+use dbus_udisks2::options::Wakeup;
 use dbus_udisks2::{Disks, UDisks2};
 use std::env::args;
+use std::path::Path;
 
 fn main() {
     match args().nth(1) {
@@ -13,7 +15,7 @@ fn print() {
     let disks = Disks::new(&udisks2);
     for device in disks.devices {
         println!("{:#?}", device);
-        if let Ok(smart_data) = udisks2.smart_attributes(&device.drive, true) {
+        if let Ok(smart_data) = udisks2.smart_attributes(&device.drive, Wakeup::Allow) {
             println!("{:#?}", smart_data);
         }
     }
@@ -21,9 +23,7 @@ fn print() {
 
 fn print_block(block_name: &str) {
     let udisks2 = UDisks2::new().unwrap();
-    for block in udisks2.get_blocks() {
-        if block.device.to_str().unwrap() == block_name {
-            println!("{:#?}", block);
-        }
+    if let Some(block) = udisks2.get_block_by_device(Path::new(block_name)) {
+        println!("{:#?}", block);
     }
 }
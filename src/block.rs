@@ -1,7 +1,67 @@
 use crate::utils::*;
-use crate::DbusObjects;
-use dbus::arg::RefArg;
-use std::path::PathBuf;
+use crate::{DbusObjects, LinkSpeed};
+use dbus::arg::{RefArg, Variant};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Bus classifiers used in `/dev/disk/by-id/` symlinks, in order of preference.
+const BY_ID_PREFIXES: &[&str] = &["ata", "nvme", "usb", "scsi", "mmc", "wwn"];
+
+/// Names of common GPT partition type GUIDs.
+const GPT_TYPE_NAMES: &[(&str, &str)] = &[
+    ("c12a7328-f81f-11d2-ba4b-00a0c93ec93b", "EFI System"),
+    ("21686148-6449-6e6f-744e-656564454649", "BIOS Boot"),
+    ("0fc63daf-8483-4772-8e79-3d69d8477de4", "Linux Filesystem"),
+    ("0657fd6d-a4ab-43c4-84e5-0933c84b4f4f", "Linux Swap"),
+    ("e6d6d379-f507-44c2-a23c-238f2a3df928", "Linux LVM"),
+    ("a19d880f-05fc-4d3b-a006-743f0f84911e", "Linux RAID"),
+    (
+        "4f68bce3-e8cd-4db1-96e7-fbcaf984b709",
+        "Linux Root (x86-64)",
+    ),
+    ("933ac7e1-2eb4-4f13-b844-0e14e2aef915", "Linux Home"),
+    ("ca7d7ccb-63ed-4c53-861c-1742536059cc", "Linux LUKS"),
+    (
+        "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7",
+        "Microsoft Basic Data",
+    ),
+    ("e3c9e316-0b5c-4db8-817d-f92df00215ae", "Microsoft Reserved"),
+    ("de94bba4-06d1-4d40-a16a-bfd50179d6ac", "Windows Recovery"),
+    ("48465300-0000-11aa-aa11-00306543ecac", "Apple HFS+"),
+    ("7c3457ef-0000-11aa-aa11-00306543ecac", "Apple APFS"),
+];
+
+/// Names of the GPT partition attribute bits, by bit.
+const GPT_FLAG_NAMES: &[(u32, &str)] = &[
+    (0, "RequiredPartition"),
+    (1, "NoBlockIOProtocol"),
+    (2, "LegacyBIOSBootable"),
+    (60, "ReadOnly"),
+    (62, "Hidden"),
+    (63, "NoAutomount"),
+];
+
+/// The bit of the DOS partition flags marking the partition as bootable.
+const DOS_BOOTABLE: u64 = 0x80;
+
+/// Names of common DOS partition type bytes.
+const DOS_TYPE_NAMES: &[(u8, &str)] = &[
+    (0x01, "FAT12"),
+    (0x05, "Extended"),
+    (0x06, "FAT16"),
+    (0x07, "NTFS/exFAT"),
+    (0x0b, "FAT32"),
+    (0x0c, "FAT32 (LBA)"),
+    (0x0e, "FAT16 (LBA)"),
+    (0x0f, "Extended (LBA)"),
+    (0x82, "Linux Swap"),
+    (0x83, "Linux"),
+    (0x85, "Linux Extended"),
+    (0x8e, "Linux LVM"),
+    (0xee, "GPT Protective"),
+    (0xef, "EFI System"),
+    (0xfd, "Linux RAID"),
+];
 
 #[derive(Clone, Debug, Default)]
 pub struct Block {
@@ -37,14 +97,204 @@ pub struct Block {
     pub table: Option<PartitionTable>,
     pub userspace_mount_options: Vec<String>,
     pub configuration: Option<BlockConfiguration>,
+    pub logical_volume: Option<LogicalVolume>,
 }
 
 impl Block {
+    /// Parse a block from the interfaces of a managed object, as returned by the
+    /// `GetManagedObjects` method of UDisks, for callers which fetch the objects themselves.
+    /// `None` if the object isn't a block, or is a loop device, having the
+    /// `org.freedesktop.UDisks2.Loop` interface. Loop devices are left out of the blocks of this
+    /// crate, as they are from [`get_blocks`][crate::UDisks2::get_blocks].
+    ///
+    /// The [`logical_volume`][Self::logical_volume] is left empty, as it's an object of its own.
+    pub fn from_dbus(path: &str, objects: &DbusObjects) -> Option<Block> {
+        Block::parse_from(path, objects)
+    }
+
     /// This will be true if this block contains an encrypted volume.
     pub fn is_encrypted(&self) -> bool {
         self.encrypted.is_some()
     }
 
+    /// Whether the block is a whole device rather than a partition, which is the case whenever
+    /// it has no `partition`. A whole disk may hold a partition table in its `table`, or, as a
+    /// superfloppy, a file system directly, or nothing at all.
+    ///
+    /// Blocks which aren't backed by a drive, such as loop devices, LVM2 logical volumes, and the
+    /// cleartext blocks of unlocked containers, aren't partitions either, and so are also whole.
+    /// Check the `drive` to tell the whole disk of a drive apart from them.
+    ///
+    /// ```
+    /// use dbus_udisks2::{Block, Partition, PartitionTable};
+    ///
+    /// let partition = Block { partition: Some(Partition::default()), ..Block::default() };
+    /// assert!(!partition.is_whole_disk());
+    ///
+    /// let partitioned = Block { table: Some(PartitionTable::default()), ..Block::default() };
+    /// assert!(partitioned.is_whole_disk());
+    ///
+    /// let superfloppy = Block {
+    ///     id_usage: Some("filesystem".into()),
+    ///     id_type: Some("vfat".into()),
+    ///     ..Block::default()
+    /// };
+    /// assert!(superfloppy.is_whole_disk());
+    /// ```
+    pub fn is_whole_disk(&self) -> bool {
+        self.partition.is_none()
+    }
+
+    /// Whether the block is a component of another device, rather than holding content of its
+    /// own to show to the user. That is the case for:
+    ///
+    /// - members of a RAID array or LVM2 physical volumes, which have an `id_usage` of `"raid"`
+    /// - members of an MD RAID array known to UDisks, which have an `mdraid_member`
+    /// - unlocked encrypted containers, whose content is that of their cleartext block
+    ///
+    /// Locked containers are not components, as the container is all there is to show.
+    pub fn is_component(&self) -> bool {
+        let is_set = |path: &str| !path.is_empty() && path != "/";
+        self.id_usage.as_deref() == Some("raid")
+            || matches!(self.mdraid_member.to_str(), Some(path) if is_set(path))
+            || matches!(self.encrypted, Some(ref e) if e.is_unlocked())
+    }
+
+    /// Whether the block is a thinly provisioned LVM2 logical volume, whose `size` may exceed
+    /// the space that is actually allocated to it. See [`LogicalVolume`] for the module which
+    /// provides this.
+    pub fn is_thin(&self) -> bool {
+        matches!(self.logical_volume, Some(ref lv) if lv.thin_pool.is_some())
+    }
+
+    /// The fraction of a thin volume's `size` that is allocated, between `0.0` and `1.0`, or
+    /// `None` if the block isn't a thin volume.
+    pub fn allocated_ratio(&self) -> Option<f64> {
+        self.logical_volume
+            .as_ref()
+            .filter(|lv| lv.thin_pool.is_some())
+            .map(|lv| lv.data_allocated_ratio)
+    }
+
+    /// Whether the file system was mounted by a user through UDisks, rather than by the system,
+    /// such as from `/etc/fstab`. Returns `None` if the block isn't mounted.
+    ///
+    /// UDisks records the mounts it performs in the userspace mount options, which libmount
+    /// stores in its utab, as `uhelper=udisks2`. Those without it are assumed to be system
+    /// mounts. A user may unmount their own mounts without authentication, so this indicates
+    /// whether an unmount is likely to need to be interactive.
+    pub fn is_user_mount(&self) -> Option<bool> {
+        if self.mount_points.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.userspace_mount_options
+                .iter()
+                .any(|option| option == "uhelper=udisks2"),
+        )
+    }
+
+    /// A best-effort prediction of the directory UDisks will mount the block at, such as
+    /// `/run/media/<user>/<label>`, for display before mounting.
+    ///
+    /// Following UDisks, the directory is named after the label, with any `/` replaced by `_`,
+    /// or the UUID if there is no label, or `disk` if neither are set. Directories are placed in
+    /// `/run/media/<user>`, or `/media/<user>` on systems without `/run/media`, where the user is
+    /// taken from the `USER` or `LOGNAME` environment variables.
+    ///
+    /// UDisks appends a number to the name if the directory already exists, and it may be
+    /// configured to mount elsewhere, so the actual mount point is only known after mounting.
+    pub fn default_mount_dir(&self) -> PathBuf {
+        let name = match (self.id_label.as_ref(), self.id_uuid.as_ref()) {
+            (Some(label), _) => label.replace('/', "_"),
+            (None, Some(uuid)) => uuid.clone(),
+            (None, None) => String::from("disk"),
+        };
+
+        let base = if Path::new("/run/media").is_dir() {
+            "/run/media"
+        } else {
+            "/media"
+        };
+
+        let user = std::env::var_os("USER")
+            .or_else(|| std::env::var_os("LOGNAME"))
+            .unwrap_or_default();
+
+        Path::new(base).join(user).join(name)
+    }
+
+    /// The type of file system on the block, such as `"ext4"`, whether mounted or not.
+    ///
+    /// This is the `id_type` probed by blkid, but only if `id_usage` is `"filesystem"`. Other
+    /// signatures also have an `id_type`, such as `"crypto_LUKS"` with a `"crypto"` usage, or
+    /// `"LVM2_member"` with a `"raid"` usage, and aren't file systems.
+    pub fn filesystem_type(&self) -> Option<&str> {
+        match self.id_usage.as_deref() {
+            Some("filesystem") => self.id_type.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The LUKS version of an encrypted container, such as `1` or `2`, or `None` if the block is
+    /// not a LUKS container.
+    ///
+    /// This is read from the block's `id_version` when its `id_type` is `"crypto_LUKS"`, which
+    /// blkid probes from the LUKS header, so the container does not need to be unlocked. The
+    /// `hint_encryption_type` of [`Encrypted`] is instead the type UDisks uses when unlocking,
+    /// and is not tied to the version on disk.
+    pub fn luks_version(&self) -> Option<u8> {
+        if self.id_type.as_deref() != Some("crypto_LUKS") {
+            return None;
+        }
+        self.id_version.as_deref()?.parse().ok()
+    }
+
+    /// Whether the block is busy, such that destructive operations like formatting would fail or
+    /// disrupt something. UDisks has no single flag for this, so it is inferred from the block
+    /// being:
+    ///
+    /// - mounted
+    /// - an active swap space
+    /// - an unlocked encrypted container
+    /// - a member of an MD RAID array
+    /// - a partition table holding any partition which is in use
+    ///
+    /// Other holders, such as LVM physical volumes or open file descriptors, are not visible
+    /// through UDisks and so are not detected. `within` should hold every block, such as from
+    /// [`get_blocks`][crate::UDisks2::get_blocks].
+    pub fn is_in_use(&self, within: &[Block]) -> bool {
+        let is_member = |path: &Path| !path.as_os_str().is_empty() && path != Path::new("/");
+
+        !self.mount_points.is_empty()
+            || self.swapspace == Some(true)
+            || self.get_encrypted_block(within).is_some()
+            || is_member(&self.mdraid_member)
+            || (self.table.is_some()
+                && within.iter().any(|block| {
+                    matches!(block.partition, Some(ref p) if p.table == self.path)
+                        && block.is_in_use(within)
+                }))
+    }
+
+    /// The label to present for the block, in UDisks' order of precedence:
+    ///
+    /// 1. `hint_name`, an override set by udev rules through `UDISKS_NAME`
+    /// 2. `id_label`, the label of the file system or other contents
+    /// 3. the name of the device node, such as `sda1`
+    ///
+    /// `None` only if none of these are known.
+    pub fn display_label(&self) -> Option<String> {
+        self.hint_name
+            .clone()
+            .or_else(|| self.id_label.clone())
+            .or_else(|| {
+                let name = self.device.file_name()?;
+                Some(name.to_string_lossy().into_owned())
+            })
+    }
+
     /// If this block contains an encrypted volume, find the block associated with it.
     pub fn get_encrypted_block<'a>(&self, within: &'a [Block]) -> Option<&'a Block> {
         if self.encrypted.is_some() {
@@ -53,6 +303,226 @@ impl Block {
             None
         }
     }
+
+    /// If this is the cleartext block of an unlocked container, find the block of the container.
+    /// This is the inverse of [`get_encrypted_block`][Self::get_encrypted_block].
+    ///
+    /// ```
+    /// # use dbus_udisks2::Block;
+    /// let container = Block {
+    ///     path: "/org/freedesktop/UDisks2/block_devices/sda2".into(),
+    ///     crypto_backing_device: "/".into(),
+    ///     ..Block::default()
+    /// };
+    /// let cleartext = Block {
+    ///     path: "/org/freedesktop/UDisks2/block_devices/dm_2d0".into(),
+    ///     crypto_backing_device: container.path.clone(),
+    ///     ..Block::default()
+    /// };
+    /// let blocks = [container.clone(), cleartext.clone()];
+    ///
+    /// assert_eq!(cleartext.backing_block(&blocks).unwrap().path, container.path);
+    /// assert!(container.backing_block(&blocks).is_none());
+    /// ```
+    pub fn backing_block<'a>(&self, within: &'a [Block]) -> Option<&'a Block> {
+        if self.crypto_backing_device.is_empty() || self.crypto_backing_device == "/" {
+            return None;
+        }
+        within.iter().find(|b| b.path == self.crypto_backing_device)
+    }
+
+    /// Whether `device` refers to this block, by its device node, its preferred device node, or
+    /// any of its symlinks. This allows matching a device mapper block by either its `/dev/dm-N`
+    /// node or its `/dev/mapper/` name.
+    ///
+    /// ```
+    /// # use dbus_udisks2::Block;
+    /// # use std::path::{Path, PathBuf};
+    /// let block = Block {
+    ///     device: PathBuf::from("/dev/dm-0"),
+    ///     preferred_device: PathBuf::from("/dev/mapper/cryptroot"),
+    ///     symlinks: vec![PathBuf::from("/dev/disk/by-id/dm-name-cryptroot")],
+    ///     ..Block::default()
+    /// };
+    ///
+    /// assert!(block.matches_device(Path::new("/dev/dm-0")));
+    /// assert!(block.matches_device(Path::new("/dev/mapper/cryptroot")));
+    /// assert!(block.matches_device(Path::new("/dev/disk/by-id/dm-name-cryptroot")));
+    /// assert!(!block.matches_device(Path::new("/dev/dm-1")));
+    /// ```
+    pub fn matches_device(&self, device: &Path) -> bool {
+        self.device == device
+            || self.preferred_device == device
+            || self.symlinks.iter().any(|link| link == device)
+    }
+
+    /// The bus classifier from the block's `/dev/disk/by-id/` symlink, for use as a fallback
+    /// when the drive's `connection_bus` is empty.
+    ///
+    /// Common prefixes are:
+    ///
+    /// - `ata`: SATA and PATA devices
+    /// - `nvme`: NVMe devices
+    /// - `usb`: USB mass storage
+    /// - `scsi`: SCSI and SAS devices
+    /// - `mmc`: SD and eMMC cards
+    /// - `wwn`: the device's World Wide Name, which does not identify a bus
+    ///
+    /// A device usually has several by-id symlinks, so a bus prefix is preferred over `wwn`.
+    /// Symlinks with other prefixes, such as `dm-name` or `md-uuid`, are ignored.
+    pub fn by_id_prefix(&self) -> Option<&str> {
+        let prefixes = self
+            .symlinks
+            .iter()
+            .filter_map(|link| link.strip_prefix("/dev/disk/by-id/").ok())
+            .filter_map(|name| name.to_str())
+            .filter_map(|name| name.split('-').next())
+            .filter_map(|prefix| BY_ID_PREFIXES.iter().position(|&p| p == prefix));
+
+        prefixes.min().map(|index| BY_ID_PREFIXES[index])
+    }
+
+    /// The major and minor numbers of the device, as used by `/proc` and `/sys/dev/block`.
+    ///
+    /// These are decoded from `device_number`, a Linux `dev_t`, in which both numbers are split:
+    /// the low 8 bits of the minor come first, then 12 bits of major, then the remaining minor
+    /// bits, and the remaining major bits at the top.
+    ///
+    /// ```
+    /// use dbus_udisks2::Block;
+    ///
+    /// let block = |device_number| Block { device_number, ..Block::default() };
+    ///
+    /// assert_eq!(block(0x0800).major_minor(), (8, 0));
+    /// assert_eq!(block(0x0811).major_minor(), (8, 17));
+    /// assert_eq!(block(0x1_0301).major_minor(), (259, 1));
+    /// assert_eq!(block(0x10_0800).major_minor(), (8, 256));
+    /// ```
+    pub fn major_minor(&self) -> (u32, u32) {
+        let dev = self.device_number;
+        let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+        let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+        (major as u32, minor as u32)
+    }
+
+    /// The options the block is actually mounted with, as reported by the kernel in
+    /// `/proc/self/mountinfo`, or `None` if the block isn't mounted, or its mount isn't found.
+    /// Unlike `userspace_mount_options`, which only records options UDisks was asked for, these
+    /// include defaults and any changes made since, such as a remount as `ro` after an error.
+    ///
+    /// The per-mount options, such as `rw` and `noatime`, come first, followed by those of the
+    /// file system which aren't already listed, such as `errors=remount-ro`. If the block is
+    /// mounted more than once, the mount at the first of its `mount_points` is used.
+    ///
+    /// Mounts are matched by the major and minor numbers of the device, which sidesteps the
+    /// naming of device mapper and loop devices, falling back to the device node for file
+    /// systems such as btrfs, which report an anonymous device number.
+    pub fn active_mount_options(&self) -> Option<Vec<String>> {
+        let mount_point = self.mount_points.first()?;
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").ok()?;
+        let (major, minor) = self.major_minor();
+        let device_number = format!("{}:{}", major, minor);
+
+        mountinfo.lines().find_map(|line| {
+            let (mount, filesystem) = line.split_at(line.find(" - ")?);
+            let mount = mount.split(' ').collect::<Vec<_>>();
+            let filesystem = filesystem[3..].split(' ').collect::<Vec<_>>();
+            let (number, target, options) = (*mount.get(2)?, *mount.get(4)?, *mount.get(5)?);
+            let (source, super_options) = (*filesystem.get(1)?, *filesystem.get(2)?);
+
+            if Path::new(&unescape_octal(target)) != mount_point {
+                return None;
+            }
+            let source = PathBuf::from(unescape_octal(source));
+            let matches = number == device_number
+                || self.matches_device(&source)
+                || matches!(source.canonicalize(), Ok(source) if self.device == source);
+            if !matches {
+                return None;
+            }
+
+            let mut active = options.split(',').map(String::from).collect::<Vec<_>>();
+            for option in super_options.split(',') {
+                if !active.iter().any(|o| o == option) {
+                    active.push(option.to_owned());
+                }
+            }
+            Some(active)
+        })
+    }
+
+    /// The size and usage of the block's swap space, read from `/proc/swaps`, or `None` if the
+    /// block is not an active swap space. UDisks only reports whether swap is active.
+    ///
+    /// Entries of `/proc/swaps` name the device as the kernel knows it, such as `/dev/dm-0`
+    /// rather than `/dev/mapper/swap`, with spaces and other special characters escaped in
+    /// octal. They're matched against the block's device and symlinks, and failing that, by
+    /// resolving the entry's symlinks. An error of the kind [`io::ErrorKind::NotFound`] is
+    /// returned if no entry matches.
+    pub fn swap_usage(&self) -> Option<io::Result<SwapStat>> {
+        if self.swapspace != Some(true) {
+            return None;
+        }
+
+        let swaps = match std::fs::read_to_string("/proc/swaps") {
+            Ok(swaps) => swaps,
+            Err(why) => return Some(Err(why)),
+        };
+
+        let stat = swaps.lines().skip(1).find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = PathBuf::from(unescape_octal(fields.next()?));
+            let size = fields.nth(1)?.parse::<u64>().ok()?;
+            let used = fields.next()?.parse::<u64>().ok()?;
+            let matches = self.matches_device(&name)
+                || matches!(name.canonicalize(), Ok(name) if self.device == name);
+            if matches {
+                Some(SwapStat {
+                    size: size * 1024,
+                    used: used * 1024,
+                })
+            } else {
+                None
+            }
+        });
+
+        Some(stat.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not listed in /proc/swaps", self.device.display()),
+            )
+        }))
+    }
+
+    /// The negotiated speed of the link to the device, such as to spot a SATA SSD running at
+    /// 3 Gbps due to a bad cable. UDisks doesn't expose this, so it is read from sysfs on a best
+    /// effort basis, walking up from `/sys/dev/block/<major>:<minor>` to the first of:
+    ///
+    /// - an `ataN` port: `/sys/class/ata_link/linkN/sata_spd` and `hw_sata_spd_limit`
+    /// - a USB device, with an `idVendor`: its `speed`
+    /// - a PCIe device: its `current_link_speed` and `current_link_width`, and their maximums
+    ///
+    /// `None` if none is found, such as for virtual devices, or if the link is down. Should be
+    /// called on a whole disk, as partitions share its link.
+    pub fn link_speed(&self) -> Option<LinkSpeed> {
+        let (major, minor) = self.major_minor();
+        crate::link::link_speed(Path::new(&format!("/sys/dev/block/{}:{}", major, minor)))
+    }
+
+    /// Whether the kernel accepts discard (TRIM) requests for this block device.
+    ///
+    /// UDisks neither exposes a discard property nor provides a method for trimming, so this is
+    /// read from the device's `queue/discard_max_bytes` in sysfs. Actually trimming a mounted
+    /// file system must go through the kernel, such as with `fstrim`.
+    pub fn supports_discard(&self) -> bool {
+        let (major, minor) = self.major_minor();
+        let path = format!("/sys/dev/block/{}:{}/queue/discard_max_bytes", major, minor);
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|max| max.trim().parse::<u64>().ok())
+            .unwrap_or(0)
+            != 0
+    }
 }
 
 impl ParseFrom for Block {
@@ -69,11 +539,11 @@ impl ParseFrom for Block {
                 for (key, ref value) in object {
                     match key.as_str() {
                         "CryptoBackingDevice" => {
-                            block.crypto_backing_device = get_string(value).unwrap()
+                            block.crypto_backing_device = get_object_path(path, key, value)
                         }
-                        "Device" => block.device = PathBuf::from(get_byte_array(value).unwrap()),
+                        "Device" => block.device = get_device(path, key, value),
                         "DeviceNumber" => block.device_number = get_u64(value),
-                        "Drive" => block.drive = get_string(value).unwrap(),
+                        "Drive" => block.drive = get_object_path(path, key, value),
                         "HintAuto" => block.hint_auto = get_bool(value),
                         "HintIconName" => block.hint_icon_name = get_string(value),
                         "HintIgnore" => block.hint_ignore = get_bool(value),
@@ -94,9 +564,7 @@ impl ParseFrom for Block {
                             block.mdraid_member =
                                 get_string(value).map(PathBuf::from).unwrap_or_default()
                         }
-                        "PreferredDevice" => {
-                            block.preferred_device = PathBuf::from(get_byte_array(value).unwrap())
-                        }
+                        "PreferredDevice" => block.preferred_device = get_device(path, key, value),
                         "ReadOnly" => block.read_only = get_bool(value),
                         "Size" => block.size = get_u64(value),
                         "Symlinks" => {
@@ -111,111 +579,11 @@ impl ParseFrom for Block {
                                 get_string_array(value).unwrap_or_default()
                         }
                         "Configuration" => {
-                            let mut configuration = BlockConfiguration::default();
-                            for value in value.as_iter().unwrap() {
-                                if let Some(mut iterator) = value.as_iter() {
-                                    if let Some(mut iterator) =
-                                        iterator.next().and_then(|i| i.as_iter())
-                                    {
-                                        if let (Some(key), Some(mut array)) = (
-                                            iterator.next(),
-                                            iterator.next().and_then(|i| i.as_iter()),
-                                        ) {
-                                            if let Some(key) = key.as_str() {
-                                                if key == "fstab" {
-                                                    while let (Some(key), Some(value)) =
-                                                        (array.next(), array.next())
-                                                    {
-                                                        if let Some(key) = key.as_str() {
-                                                            match key {
-                                                                "fsname" => {
-                                                                    configuration.fstab.fsname =
-                                                                        vva(value)
-                                                                            .unwrap_or_default()
-                                                                }
-                                                                "dir" => {
-                                                                    configuration.fstab.dir =
-                                                                        vva(value)
-                                                                            .unwrap_or_default()
-                                                                }
-                                                                "type" => {
-                                                                    configuration.fstab.type_ =
-                                                                        vva(value)
-                                                                            .unwrap_or_default()
-                                                                }
-                                                                "opts" => {
-                                                                    configuration.fstab.opts =
-                                                                        vva(value)
-                                                                            .unwrap_or_default()
-                                                                }
-                                                                "freq" => {
-                                                                    configuration.fstab.freq = value
-                                                                        .as_u64()
-                                                                        .unwrap_or_default()
-                                                                        as i32
-                                                                }
-                                                                "passno" => {
-                                                                    configuration.fstab.passno =
-                                                                        value
-                                                                            .as_u64()
-                                                                            .unwrap_or_default()
-                                                                            as i32
-                                                                }
-                                                                _ => {
-                                                                    eprintln!("unhandled block config fstab key: {:?}, {:?}", key, value);
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                } else if key == "crypttab" {
-                                                    while let (Some(key), Some(value)) =
-                                                        (array.next(), array.next())
-                                                    {
-                                                        if let Some(key) = key.as_str() {
-                                                            match key {
-                                                                "name" => {
-                                                                    configuration.crypttab.name =
-                                                                        vva(value)
-                                                                            .unwrap_or_default()
-                                                                }
-                                                                "device" => {
-                                                                    configuration.crypttab.device =
-                                                                        vva(value)
-                                                                            .unwrap_or_default()
-                                                                }
-                                                                "passphrase-path" => {
-                                                                    configuration
-                                                                        .crypttab
-                                                                        .passphrase_path =
-                                                                        vva(value)
-                                                                            .unwrap_or_default()
-                                                                }
-                                                                "options" => {
-                                                                    configuration.crypttab.options =
-                                                                        vva(value)
-                                                                            .unwrap_or_default()
-                                                                }
-                                                                _ => {
-                                                                    eprintln!("unhandled block config crypttab key: {:?}, {:?}", key, value);
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                } else {
-                                                    eprintln!("unknown block config key: {}", key);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-
-                            block.configuration = Some(configuration);
+                            block.configuration = Some(parse_configuration(&value.0))
                         }
                         _ => {
-                            #[cfg(debug_assertions)]
-                            eprintln!("unhandled org.freedesktop.UDisks2.Block.{}", key);
-                            eprintln!("value: {:#?}", value);
+                            log::debug!("unhandled org.freedesktop.UDisks2.Block.{}", key);
+                            log::trace!("value: {:#?}", value);
                         }
                     }
                 }
@@ -239,8 +607,7 @@ impl ParseFrom for Block {
                                 table.partitions.sort_unstable();
                             }
                             _ => {
-                                #[cfg(debug_assertions)]
-                                eprintln!(
+                                log::debug!(
                                     "unhandled org.freedesktop.UDisks2.PartitionTable.{}",
                                     key
                                 );
@@ -257,10 +624,7 @@ impl ParseFrom for Block {
                             "Type" => partition.type_ = get_string(value).unwrap_or_default(),
                             "Name" => partition.name = get_string(value).unwrap_or_default(),
                             "UUID" => partition.uuid = get_string(value).unwrap_or_default(),
-                            "Table" => {
-                                partition.table =
-                                    get_string(value).expect("partition is not part of a table")
-                            }
+                            "Table" => partition.table = get_object_path(path, key, value),
                             "Flags" => partition.flags = get_u64(value),
                             "Offset" => partition.offset = get_u64(value),
                             "Size" => partition.size = get_u64(value),
@@ -268,8 +632,7 @@ impl ParseFrom for Block {
                             "IsContained" => partition.is_contained = get_bool(value),
                             "IsContainer" => partition.is_container = get_bool(value),
                             _ => {
-                                #[cfg(debug_assertions)]
-                                eprintln!("unhandled org.freedesktop.UDisks2.Partition.{}", key);
+                                log::debug!("unhandled org.freedesktop.UDisks2.Partition.{}", key);
                             }
                         }
                     }
@@ -295,18 +658,29 @@ impl ParseFrom for Block {
                             "CleartextDevice" => {
                                 encrypted.cleartext_device = get_string(value).unwrap_or_default()
                             }
+                            "ChildConfiguration" => {
+                                encrypted.child_configuration = Some(parse_configuration(&value.0))
+                            }
                             _ => {
-                                #[cfg(debug_assertions)]
-                                eprintln!("unhandled org.freedesktop.UDisks2.Encrypted.{}", key);
+                                log::debug!("unhandled org.freedesktop.UDisks2.Encrypted.{}", key);
                             }
                         }
                     }
 
                     block.encrypted = Some(encrypted);
                 }
+                "org.freedesktop.UDisks2.Block.LVM2" => {
+                    block.logical_volume =
+                        object
+                            .get("LogicalVolume")
+                            .and_then(get_string)
+                            .map(|path| LogicalVolume {
+                                path,
+                                ..LogicalVolume::default()
+                            });
+                }
                 _ => {
-                    #[cfg(debug_assertions)]
-                    eprintln!("unhandled org.freedesktop.UDisks2.{}", key);
+                    log::debug!("unhandled org.freedesktop.UDisks2.{}", key);
                 }
             }
         }
@@ -315,10 +689,147 @@ impl ParseFrom for Block {
     }
 }
 
+/// Parse the `a(sa{sv})` configuration items of a block.
+fn parse_configuration(items: &dyn RefArg) -> BlockConfiguration {
+    let mut configuration = BlockConfiguration::default();
+    let items = match items.as_iter() {
+        Some(items) => items,
+        None => return configuration,
+    };
+
+    for item in items {
+        let mut item = match item.as_iter() {
+            Some(item) => item,
+            None => continue,
+        };
+
+        if let (Some(key), Some(details)) = (
+            item.next().and_then(|key| key.as_str()),
+            item.next().and_then(|details| details.as_iter()),
+        ) {
+            match key {
+                "fstab" => configuration.fstab.push(parse_fstab(details)),
+                "crypttab" => configuration.crypttab.push(parse_crypttab(details)),
+                _ => log::debug!("unknown block config key: {}", key),
+            }
+        }
+    }
+
+    configuration
+}
+
+fn parse_fstab<'a>(
+    mut details: Box<dyn Iterator<Item = &'a dyn RefArg> + 'a>,
+) -> BlockConfigurationFstab {
+    let mut fstab = BlockConfigurationFstab::default();
+    while let (Some(key), Some(value)) = (details.next(), details.next()) {
+        if let Some(key) = key.as_str() {
+            match key {
+                "fsname" => fstab.fsname = vva(value).unwrap_or_default(),
+                "dir" => fstab.dir = vva(value).unwrap_or_default(),
+                "type" => fstab.type_ = vva(value).unwrap_or_default(),
+                "opts" => fstab.opts = vva(value).unwrap_or_default(),
+                "freq" => fstab.freq = value.as_i64().unwrap_or_default() as i32,
+                "passno" => fstab.passno = value.as_i64().unwrap_or_default() as i32,
+                _ => log::debug!("unhandled block config fstab key: {:?}, {:?}", key, value),
+            }
+        }
+    }
+    fstab
+}
+
+fn parse_crypttab<'a>(
+    mut details: Box<dyn Iterator<Item = &'a dyn RefArg> + 'a>,
+) -> BlockConfigurationCrypttab {
+    let mut crypttab = BlockConfigurationCrypttab::default();
+    while let (Some(key), Some(value)) = (details.next(), details.next()) {
+        if let Some(key) = key.as_str() {
+            match key {
+                "name" => crypttab.name = vva(value).unwrap_or_default(),
+                "device" => crypttab.device = vva(value).unwrap_or_default(),
+                "passphrase-path" => crypttab.passphrase_path = vva(value).unwrap_or_default(),
+                "options" => crypttab.options = vva(value).unwrap_or_default(),
+                _ => log::debug!(
+                    "unhandled block config crypttab key: {:?}, {:?}",
+                    key,
+                    value
+                ),
+            }
+        }
+    }
+    crypttab
+}
+
+/// Read a device node from a byte array, which may be malformed or missing if the device is
+/// being removed while the objects are fetched. An empty path is used in that case, so that the
+/// rest of the block still parses.
+fn get_device(path: &str, key: &str, value: &Variant<Box<dyn RefArg>>) -> PathBuf {
+    match get_byte_array(value) {
+        Some(device) => PathBuf::from(device),
+        None => {
+            log::debug!(
+                "malformed org.freedesktop.UDisks2.Block::{} of {}",
+                key,
+                path
+            );
+            PathBuf::new()
+        }
+    }
+}
+
+/// Read an object path, which may be malformed for the same reason as the device in
+/// [`get_device`]. `"/"`, which UDisks uses to refer to no object, is used in that case.
+fn get_object_path(path: &str, key: &str, value: &Variant<Box<dyn RefArg>>) -> String {
+    match get_string(value) {
+        Some(object) => object,
+        None => {
+            log::debug!("malformed object path {} of {}", key, path);
+            String::from("/")
+        }
+    }
+}
+
+/// Undo the octal escapes, such as `\040` for a space, used by the kernel in `/proc` tables.
+fn unescape_octal(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 4).and_then(|digits| {
+            let digits = std::str::from_utf8(digits).ok()?;
+            u8::from_str_radix(digits, 8).ok()
+        });
+        match escaped {
+            Some(byte) if bytes[i] == b'\\' => {
+                output.push(byte);
+                i += 4;
+            }
+            _ => {
+                output.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+impl<'a> From<&'a Block> for dbus::Path<'a> {
+    fn from(block: &'a Block) -> Self {
+        (&block.path).into()
+    }
+}
+
+/// The size and usage of an active swap space, in bytes. Returned by [`Block::swap_usage`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SwapStat {
+    pub size: u64,
+    pub used: u64,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct BlockConfiguration {
-    pub fstab: BlockConfigurationFstab,
-    pub crypttab: BlockConfigurationCrypttab,
+    pub fstab: Vec<BlockConfigurationFstab>,
+    pub crypttab: Vec<BlockConfigurationCrypttab>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -331,6 +842,24 @@ pub struct BlockConfigurationFstab {
     pub passno: i32,
 }
 
+impl BlockConfigurationFstab {
+    /// The configuration item details, as expected by UDisks.
+    pub(crate) fn to_dbus(&self) -> KeyVariant {
+        let mut details = KeyVariant::new();
+        for &(key, value) in &[
+            ("fsname", &self.fsname),
+            ("dir", &self.dir),
+            ("type", &self.type_),
+            ("opts", &self.opts),
+        ] {
+            details.insert(key.to_owned(), Variant(Box::new(strtoa(value))));
+        }
+        details.insert("freq".to_owned(), Variant(Box::new(self.freq)));
+        details.insert("passno".to_owned(), Variant(Box::new(self.passno)));
+        details
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct BlockConfigurationCrypttab {
     pub name: String,
@@ -339,11 +868,57 @@ pub struct BlockConfigurationCrypttab {
     pub options: String,
 }
 
+/// The LVM2 logical volume of a block.
+///
+/// These are only provided by UDisks once its `lvm2` module has been loaded, either by
+/// [`enable_modules`][crate::UDisks2::enable_modules], or by setting
+/// `modules_load_preference=ondemand` in `udisks2.conf`. The volume itself is a
+/// `org.freedesktop.UDisks2.LogicalVolume` object, referenced by the `Block.LVM2` interface.
+#[derive(Clone, Debug, Default)]
+pub struct LogicalVolume {
+    /// The path of the `org.freedesktop.UDisks2.LogicalVolume` object.
+    pub path: String,
+    /// The thin pool that the volume allocates its data from, if it is thinly provisioned.
+    pub thin_pool: Option<String>,
+    /// The fraction of the volume's data that has been allocated, between `0.0` and `1.0`. Only
+    /// meaningful for thin volumes.
+    pub data_allocated_ratio: f64,
+}
+
+/// The `org.freedesktop.UDisks2.Encrypted` interface of a block, parsed in full.
+///
+/// UDisks doesn't indicate whether a LUKS container uses a detached header: it only probes the
+/// block itself, so a container whose header is stored elsewhere isn't recognized as encrypted
+/// in the first place, and has no `Encrypted` interface. A detached header may instead be spotted
+/// through a `header=` option among the `crypttab` entries of the block's `configuration`.
 #[derive(Clone, Debug, Default)]
 pub struct Encrypted {
+    /// The type of encryption used, such as `"luks1"` or `"luks2"`, or empty if unknown.
     pub hint_encryption_type: String,
+    /// The size of the metadata at the start of the container, in bytes, or `0` if unknown.
     pub metadata_size: u64,
+    /// The object path of the cleartext block while unlocked, or `"/"` while locked.
     pub cleartext_device: String,
+    /// The configuration of the cleartext block, such as its `fstab` entry, which UDisks reads
+    /// from the system configuration files even while the container is locked.
+    pub child_configuration: Option<BlockConfiguration>,
+}
+
+impl Encrypted {
+    /// Whether the container is unlocked, having a cleartext block.
+    pub fn is_unlocked(&self) -> bool {
+        !self.cleartext_device.is_empty() && self.cleartext_device != "/"
+    }
+}
+
+/// The scheme of a partition table, from the `type_` of a [`PartitionTable`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum PartitionTableType {
+    /// A GUID Partition Table, reported by UDisks as `"gpt"`.
+    Gpt,
+    /// A DOS, or MBR, partition table, reported by UDisks as `"dos"`.
+    Dos,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -369,3 +944,228 @@ pub struct Partition {
     pub is_container: bool,
     pub is_contained: bool,
 }
+
+impl PartitionTable {
+    /// The scheme of the table, or `None` if it is neither GPT nor DOS.
+    pub fn table_type(&self) -> Option<PartitionTableType> {
+        match self.type_.as_str() {
+            "gpt" => Some(PartitionTableType::Gpt),
+            "dos" => Some(PartitionTableType::Dos),
+            _ => None,
+        }
+    }
+}
+
+impl Partition {
+    /// The partition type GUID, such as `"c12a7328-f81f-11d2-ba4b-00a0c93ec93b"` for an EFI
+    /// system partition, if this is a partition of a GPT.
+    ///
+    /// `type_` is overloaded by the scheme of the partition table: UDisks reports the lowercase
+    /// type GUID on GPT, and the type byte as a hexadecimal string, such as `"0x83"`, on DOS.
+    pub fn type_guid(&self) -> Option<&str> {
+        let is_guid = self.type_.len() == 36
+            && self.type_.char_indices().all(|(i, c)| match i {
+                8 | 13 | 18 | 23 => c == '-',
+                _ => c.is_ascii_hexdigit(),
+            });
+        if is_guid {
+            Some(&self.type_)
+        } else {
+            None
+        }
+    }
+
+    /// The partition type byte, if this is a partition of a DOS table. See
+    /// [`type_guid`][Self::type_guid] for how `type_` is encoded.
+    ///
+    /// ```
+    /// # use dbus_udisks2::Partition;
+    /// let partition = |type_: &str| Partition { type_: type_.into(), ..Partition::default() };
+    ///
+    /// assert_eq!(partition("0x83").type_mbr(), Some(0x83));
+    /// assert_eq!(partition("0x0c").type_mbr(), Some(0x0c));
+    /// assert_eq!(partition("0fc63daf-8483-4772-8e79-3d69d8477de4").type_mbr(), None);
+    /// ```
+    pub fn type_mbr(&self) -> Option<u8> {
+        let byte = self.type_.strip_prefix("0x")?;
+        u8::from_str_radix(byte, 16).ok()
+    }
+
+    /// The names of the flags set on the partition, decoded according to the scheme of the
+    /// partition table which the partition belongs to. Unknown bits are left out, but remain in
+    /// `flags`.
+    ///
+    /// - GPT: `flags` holds the 64 attribute bits of the partition entry. Bits 0 to 2 are defined
+    ///   for every partition as `RequiredPartition`, `NoBlockIOProtocol` and
+    ///   `LegacyBIOSBootable`. Bits 60, 62 and 63 are `ReadOnly`, `Hidden` and `NoAutomount`,
+    ///   which are only defined for Microsoft basic data partitions, but used as such by others.
+    /// - DOS: `flags` holds the boot indicator byte, which is `0x80` for `Bootable`.
+    pub fn flag_names(&self, table_type: PartitionTableType) -> Vec<&'static str> {
+        match table_type {
+            PartitionTableType::Gpt => GPT_FLAG_NAMES
+                .iter()
+                .filter(|&&(bit, _)| self.flags & (1 << bit) != 0)
+                .map(|&(_, name)| name)
+                .collect(),
+            PartitionTableType::Dos if self.flags & DOS_BOOTABLE != 0 => vec!["Bootable"],
+            PartitionTableType::Dos => Vec::new(),
+        }
+    }
+
+    /// A name for common partition types, such as `"EFI System"` or `"Linux Swap"`, read
+    /// according to the scheme of the partition table which the partition belongs to. `None` for
+    /// types which are not known, or which do not match the scheme.
+    pub fn type_name(&self, table_type: PartitionTableType) -> Option<&'static str> {
+        match table_type {
+            PartitionTableType::Gpt => {
+                let guid = self.type_guid()?;
+                GPT_TYPE_NAMES
+                    .iter()
+                    .find(|(known, _)| known.eq_ignore_ascii_case(guid))
+                    .map(|&(_, name)| name)
+            }
+            PartitionTableType::Dos => {
+                let byte = self.type_mbr()?;
+                DOS_TYPE_NAMES
+                    .iter()
+                    .find(|(known, _)| *known == byte)
+                    .map(|&(_, name)| name)
+            }
+        }
+    }
+}
+
+impl LogicalVolume {
+    /// Fill in the details of the volume from its `org.freedesktop.UDisks2.LogicalVolume`.
+    pub(crate) fn parse(&mut self, object: &KeyVariant) {
+        for (key, value) in object {
+            match key.as_str() {
+                "ThinPool" => {
+                    self.thin_pool = get_string(value).filter(|path| path != "/");
+                }
+                "DataAllocatedRatio" => self.data_allocated_ratio = get_f64(value),
+                _ => {
+                    log::debug!("unhandled org.freedesktop.UDisks2.LogicalVolume.{}", key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbus::Message;
+
+    fn entry(fields: &[(&str, &str)], numbers: &[(&str, i32)]) -> KeyVariant {
+        let mut details = KeyVariant::new();
+        for &(key, value) in fields {
+            details.insert(key.to_owned(), Variant(Box::new(strtoa(value))));
+        }
+        for &(key, value) in numbers {
+            details.insert(key.to_owned(), Variant(Box::new(value)));
+        }
+        details
+    }
+
+    #[test]
+    fn parses_malformed_block() {
+        // A partition being removed while the objects are fetched, whose paths have been emptied
+        // or have the wrong type.
+        let mut block = KeyVariant::new();
+        block.insert("Device".into(), Variant(Box::new(String::new())));
+        block.insert(
+            "CryptoBackingDevice".into(),
+            Variant(Box::new(String::new())),
+        );
+        block.insert("Drive".into(), Variant(Box::new(0u32)));
+        block.insert("Size".into(), Variant(Box::new(1u64 << 20)));
+        let mut partition = KeyVariant::new();
+        partition.insert("Table".into(), Variant(Box::new(String::new())));
+        partition.insert("Number".into(), Variant(Box::new(1u32)));
+
+        let mut objects = DbusObjects::new();
+        objects.insert("org.freedesktop.UDisks2.Block".into(), block);
+        objects.insert("org.freedesktop.UDisks2.Partition".into(), partition);
+
+        let block =
+            Block::from_dbus("/org/freedesktop/UDisks2/block_devices/sdb1", &objects).unwrap();
+        assert_eq!(block.device, Path::new(""));
+        assert_eq!(block.crypto_backing_device, "/");
+        assert_eq!(block.drive, "/");
+        assert_eq!(block.size, 1 << 20);
+        let partition = block.partition.unwrap();
+        assert_eq!(partition.table, "/");
+        assert_eq!(partition.number, 1);
+    }
+
+    #[test]
+    fn parses_configuration() {
+        let items: Vec<(String, KeyVariant)> = vec![
+            (
+                "fstab".to_owned(),
+                entry(
+                    &[
+                        ("fsname", "UUID=1234"),
+                        ("dir", "/"),
+                        ("type", "ext4"),
+                        ("opts", "defaults"),
+                    ],
+                    &[("freq", 0), ("passno", 1)],
+                ),
+            ),
+            (
+                "crypttab".to_owned(),
+                entry(
+                    &[
+                        ("name", "cryptdata"),
+                        ("device", "UUID=abcd"),
+                        ("passphrase-path", "/etc/keys/data"),
+                        ("options", "luks,discard"),
+                    ],
+                    &[],
+                ),
+            ),
+            (
+                "fstab".to_owned(),
+                entry(
+                    &[
+                        ("fsname", "/dev/mapper/cryptdata"),
+                        ("dir", "/mnt/my data"),
+                        ("type", "xfs"),
+                        ("opts", "nofail"),
+                    ],
+                    &[("freq", 0), ("passno", 2)],
+                ),
+            ),
+        ];
+
+        // Round trip through a message, so that the value is decoded as it is from the bus.
+        let message = Message::new_method_call("org.example", "/", "org.example", "Test")
+            .unwrap()
+            .append1(Variant(items));
+        let value: Variant<Box<dyn RefArg>> = message.read1().unwrap();
+        let configuration = parse_configuration(&value.0);
+
+        assert_eq!(configuration.fstab.len(), 2);
+        let root = &configuration.fstab[0];
+        assert_eq!(root.fsname, "UUID=1234");
+        assert_eq!(root.dir, "/");
+        assert_eq!(root.type_, "ext4");
+        assert_eq!(root.opts, "defaults");
+        assert_eq!((root.freq, root.passno), (0, 1));
+        let data = &configuration.fstab[1];
+        assert_eq!(data.fsname, "/dev/mapper/cryptdata");
+        assert_eq!(data.dir, "/mnt/my data");
+        assert_eq!(data.type_, "xfs");
+        assert_eq!(data.opts, "nofail");
+        assert_eq!((data.freq, data.passno), (0, 2));
+
+        assert_eq!(configuration.crypttab.len(), 1);
+        let crypttab = &configuration.crypttab[0];
+        assert_eq!(crypttab.name, "cryptdata");
+        assert_eq!(crypttab.device, "UUID=abcd");
+        assert_eq!(crypttab.passphrase_path, "/etc/keys/data");
+        assert_eq!(crypttab.options, "luks,discard");
+    }
+}
@@ -17,24 +17,246 @@ pub struct DiskDevice {
     pub partitions: Vec<Block>,
 }
 
+/// The differences between two [`Disks`], as returned by [`Disks::diff`]. Devices are identified
+/// by the object paths of their drives, and blocks by their own object paths.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DisksDiff {
+    /// Drives of devices which were not present before.
+    pub added_devices: Vec<String>,
+    /// Drives of devices which are no longer present.
+    pub removed_devices: Vec<String>,
+    /// Drives of devices present in both, where the size of the drive changed, or any of its
+    /// blocks were added, removed or changed.
+    pub changed_devices: Vec<String>,
+    /// Blocks which were not present before.
+    pub added_blocks: Vec<String>,
+    /// Blocks which are no longer present.
+    pub removed_blocks: Vec<String>,
+    /// Blocks present in both, whose size, mount points, or label changed.
+    pub changed_blocks: Vec<String>,
+}
+
+impl DisksDiff {
+    /// Whether nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added_devices.is_empty()
+            && self.removed_devices.is_empty()
+            && self.changed_devices.is_empty()
+            && self.added_blocks.is_empty()
+            && self.removed_blocks.is_empty()
+            && self.changed_blocks.is_empty()
+    }
+}
+
+/// A block paired with key fields of the drive which owns it, as returned by [`Disks::flatten`].
+#[derive(Debug, Clone, Copy)]
+pub struct FlatBlock<'a> {
+    /// The parent block of a device, or one of its partitions.
+    pub block: &'a Block,
+    /// The model of the owning drive.
+    pub model: &'a str,
+    /// The serial of the owning drive.
+    pub serial: &'a str,
+}
+
+/// A region of a partitioned device, as returned by [`DiskDevice::layout`].
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum Region {
+    /// Space occupied by a partition.
+    Partition(Block),
+    /// Unallocated space where a partition could be created.
+    Free { offset: u64, size: u64 },
+}
+
+/// Space reserved by a GPT at the start of the device: the protective MBR, header, and partition
+/// entries. This is the larger of the 512- and 4096-byte logical sector cases.
+const GPT_RESERVED_START: u64 = 6 * 4096;
+/// Space reserved by a GPT at the end of the device: the backup partition entries and header.
+const GPT_RESERVED_END: u64 = 5 * 4096;
+/// Space reserved by a MBR at the start of the device.
+const DOS_RESERVED_START: u64 = 512;
+/// The number of partition entries in a GPT, as created by UDisks and most other tools.
+const GPT_MAX_PARTITIONS: usize = 128;
+/// The number of primary partitions a MBR can hold, including an extended partition.
+const DOS_MAX_PRIMARY: usize = 4;
+
+impl DiskDevice {
+    /// The negotiated speed of the link to the drive, read from its parent block. See
+    /// [`Block::link_speed`].
+    pub fn link_speed(&self) -> Option<LinkSpeed> {
+        self.parent.link_speed()
+    }
+
+    /// Assemble a device from its parts, such as to build fixtures for tests without D-Bus. The
+    /// partitions are sorted by their offsets.
+    pub fn new(drive: Drive, parent: Block, mut partitions: Vec<Block>) -> Self {
+        partitions.sort_by_key(|p| p.partition.as_ref().map_or(0, |p| p.offset));
+        DiskDevice {
+            drive,
+            parent,
+            partitions,
+        }
+    }
+
+    /// Round a size down to a multiple of `alignment`, such as
+    /// [`DEFAULT_ALIGNMENT`][crate::partition::DEFAULT_ALIGNMENT], so that a partition which
+    /// starts on an aligned offset also ends on one. The result never exceeds `bytes`, and an
+    /// `alignment` of zero leaves it as it is.
+    ///
+    /// To align the offset as well, see [`align_region`][crate::partition::align_region], which
+    /// rounds the offset up and the end down.
+    pub fn align_size(&self, bytes: u64, alignment: u64) -> u64 {
+        match alignment {
+            0 => bytes,
+            alignment => bytes - bytes % alignment,
+        }
+    }
+
+    /// Whether the partition table has room for another primary partition.
+    ///
+    /// A GPT holds up to 128 partitions, all of which are primary. A MBR (`dos`) holds at most 4
+    /// primary partitions, one of which may be an extended partition. Devices without a partition
+    /// table can't have partitions. Other table types are left for UDisks to decide.
+    pub fn can_create_primary(&self) -> bool {
+        match self.parent.table.as_ref().map(|t| t.type_.as_str()) {
+            Some("gpt") => self.partitions.len() < GPT_MAX_PARTITIONS,
+            Some("dos") => self.primary_count() < DOS_MAX_PRIMARY,
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Check whether a partition of any kind may be created, before calling UDisks, which fails
+    /// with a less descriptive error.
+    ///
+    /// Beyond the limits of [`can_create_primary`][Self::can_create_primary], a MBR with an
+    /// extended partition may always hold further logical partitions within it. Whether there
+    /// is enough free space is not checked; see [`layout`][Self::layout].
+    pub fn can_create_partition(&self) -> Result<()> {
+        if self.parent.table.is_none() {
+            return Err(UdisksError::NoPartitionTable(self.parent.path.clone()));
+        }
+
+        let has_extended = self
+            .partitions
+            .iter()
+            .any(|p| matches!(p.partition, Some(ref p) if p.is_container));
+
+        if self.can_create_primary() || has_extended {
+            Ok(())
+        } else {
+            Err(UdisksError::PartitionTableFull(self.parent.path.clone()))
+        }
+    }
+
+    /// The number of partitions which aren't logical partitions within an extended partition.
+    fn primary_count(&self) -> usize {
+        self.partitions
+            .iter()
+            .filter(|p| matches!(p.partition, Some(ref p) if !p.is_contained))
+            .count()
+    }
+
+    /// The parent block followed by the partitions.
+    fn blocks(&self) -> impl Iterator<Item = &Block> {
+        std::iter::once(&self.parent).chain(&self.partitions)
+    }
+
+    /// Pairs of partitions whose byte ranges intersect, which indicates a corrupt partition
+    /// table.
+    ///
+    /// The logical partitions of a MBR table are contained within the range of its extended
+    /// partition by design, so a container partition is never reported as overlapping with a
+    /// contained one. Overlaps between two logical partitions, or between an extended partition
+    /// and a primary partition, are reported.
+    pub fn overlapping_partitions(&self) -> Vec<(&Block, &Block)> {
+        let mut overlapping = Vec::new();
+        for (i, a) in self.partitions.iter().enumerate() {
+            for b in &self.partitions[i + 1..] {
+                let (pa, pb) = match (a.partition.as_ref(), b.partition.as_ref()) {
+                    (Some(pa), Some(pb)) => (pa, pb),
+                    _ => continue,
+                };
+
+                if (pa.is_container && pb.is_contained) || (pa.is_contained && pb.is_container) {
+                    continue;
+                }
+
+                if pa.offset < pb.offset + pb.size && pb.offset < pa.offset + pa.size {
+                    overlapping.push((a, b));
+                }
+            }
+        }
+        overlapping
+    }
+
+    /// The partitions of the device, interleaved with the unallocated space between them.
+    ///
+    /// Space reserved by the partition table itself is never reported as free. Devices without a
+    /// partition table have no layout.
+    pub fn layout(&self) -> Vec<Region> {
+        let (start, end) = match self.parent.table.as_ref().map(|t| t.type_.as_str()) {
+            Some("gpt") => (
+                GPT_RESERVED_START,
+                self.parent.size.saturating_sub(GPT_RESERVED_END),
+            ),
+            Some(_) => (DOS_RESERVED_START, self.parent.size),
+            None => return Vec::new(),
+        };
+
+        let mut regions = Vec::new();
+        let mut cursor = start;
+        for block in &self.partitions {
+            let partition = match block.partition.as_ref() {
+                Some(partition) => partition,
+                None => continue,
+            };
+
+            if partition.offset > cursor {
+                regions.push(Region::Free {
+                    offset: cursor,
+                    size: partition.offset - cursor,
+                });
+            }
+
+            cursor = cursor.max(partition.offset + partition.size);
+            regions.push(Region::Partition(block.clone()));
+        }
+
+        if end > cursor {
+            regions.push(Region::Free {
+                offset: cursor,
+                size: end - cursor,
+            });
+        }
+
+        regions
+    }
+}
+
 impl Disks {
     fn new_cache(udisks2: &DiskCache) -> Self {
         let mut devices = Vec::new();
 
-        let mut blocks = Vec::new();
+        // Group the blocks by their drive once, rather than scanning every block for each drive.
+        let mut blocks_of_drive: HashMap<String, Vec<Block>> = HashMap::new();
         for block in udisks2.get_blocks() {
-            blocks.push(block);
+            blocks_of_drive
+                .entry(block.drive.clone())
+                .or_default()
+                .push(block);
         }
 
         for drive in udisks2.get_drives() {
             let mut partitions = Vec::new();
             let mut parent = None;
 
-            for block in blocks.iter().filter(|b| b.drive == drive.path) {
+            for block in blocks_of_drive.remove(&drive.path).unwrap_or_default() {
                 if block.table.is_some() {
-                    parent = Some(block.to_owned());
+                    parent = Some(block);
                 } else {
-                    partitions.push(block.to_owned());
+                    partitions.push(block);
                 }
             }
 
@@ -57,6 +279,94 @@ impl Disks {
 
         Disks { devices }
     }
+
+    /// Compare against an earlier snapshot, for detecting changes without listening for signals.
+    ///
+    /// Blocks are compared by their size, mount points, and label. Other properties are ignored.
+    pub fn diff(&self, previous: &Disks) -> DisksDiff {
+        let mut diff = DisksDiff::default();
+
+        for device in &self.devices {
+            let path = &device.drive.path;
+            let old = match previous.devices.iter().find(|d| d.drive.path == *path) {
+                Some(old) => old,
+                None => {
+                    diff.added_devices.push(path.clone());
+                    diff.added_blocks
+                        .extend(device.blocks().map(|b| b.path.clone()));
+                    continue;
+                }
+            };
+
+            let mut changed = device.drive.size != old.drive.size;
+            for block in device.blocks() {
+                match old.blocks().find(|b| b.path == block.path) {
+                    Some(old_block) if block_changed(block, old_block) => {
+                        diff.changed_blocks.push(block.path.clone());
+                        changed = true;
+                    }
+                    Some(_) => (),
+                    None => {
+                        diff.added_blocks.push(block.path.clone());
+                        changed = true;
+                    }
+                }
+            }
+            for old_block in old.blocks() {
+                if !device.blocks().any(|b| b.path == old_block.path) {
+                    diff.removed_blocks.push(old_block.path.clone());
+                    changed = true;
+                }
+            }
+
+            if changed {
+                diff.changed_devices.push(path.clone());
+            }
+        }
+
+        for old in &previous.devices {
+            if !self.devices.iter().any(|d| d.drive.path == old.drive.path) {
+                diff.removed_devices.push(old.drive.path.clone());
+                diff.removed_blocks
+                    .extend(old.blocks().map(|b| b.path.clone()));
+            }
+        }
+
+        diff
+    }
+
+    /// Find the device that the given block is either the parent or a partition of.
+    pub fn device_for_block<'a>(&'a self, block: &Block) -> Option<&'a DiskDevice> {
+        self.devices.iter().find(|device| {
+            device.parent.path == block.path
+                || device.partitions.iter().any(|p| p.path == block.path)
+        })
+    }
+
+    /// One entry per block, regardless of which drive it belongs to, for table views.
+    ///
+    /// For each device, the parent block is listed first, followed by its partitions in order of
+    /// their offsets. Every entry carries the model and serial of the drive that owns the block.
+    pub fn flatten(&self) -> Vec<FlatBlock<'_>> {
+        self.devices
+            .iter()
+            .flat_map(|device| {
+                std::iter::once(&device.parent)
+                    .chain(&device.partitions)
+                    .map(move |block| FlatBlock {
+                        block,
+                        model: &device.drive.model,
+                        serial: &device.drive.serial,
+                    })
+            })
+            .collect()
+    }
+
+    /// Collect devices that were assembled elsewhere, such as fixtures for tests without D-Bus.
+    pub fn from_devices(devices: Vec<DiskDevice>) -> Self {
+        Disks { devices }
+    }
+
     pub fn new(udisks2: &UDisks2) -> Self {
         Disks::new_cache(&udisks2.cache)
     }
@@ -65,3 +375,9 @@ impl Disks {
         Disks::new_cache(&udisks2.cache)
     }
 }
+
+fn block_changed(block: &Block, old: &Block) -> bool {
+    block.size != old.size
+        || block.mount_points != old.mount_points
+        || block.id_label != old.id_label
+}
@@ -1,6 +1,39 @@
 use crate::utils::*;
-use crate::DbusObjects;
+use crate::{DbusObjects, LinkSpeed};
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+pub(crate) const DEST: &str = "org.freedesktop.UDisks2.Drive";
+pub(crate) const EJECT: &str = "Eject";
+#[cfg(feature = "futures")]
+pub(crate) const POWER_OFF: &str = "PowerOff";
+#[cfg(feature = "futures")]
+pub(crate) const MEDIA_AVAILABLE: &str = "MediaAvailable";
+
+/// Values of `connection_bus` for buses which support hotplugging.
+const HOTPLUG_BUSES: &[&str] = &["usb", "ieee1394", "sdio"];
+
+/// The ATA devices of libata, each with the IDENTIFY DEVICE data read from the drive as its `id`.
+const ATA_DEVICES: &str = "/sys/class/ata_device";
+/// The NVMe controllers, each with its `serial`.
+const NVME_CONTROLLERS: &str = "/sys/class/nvme";
+/// The USB devices and their interfaces, with a `serial` for devices which report one.
+const USB_DEVICES: &str = "/sys/bus/usb/devices";
+/// The words of the IDENTIFY DEVICE data holding the serial number.
+const ATA_SERIAL: Range<usize> = 10..20;
+/// The word of the IDENTIFY DEVICE data holding the size of the buffer, in 512 byte sectors.
+const ATA_BUFFER_SIZE: usize = 21;
+
+/// Suffixes of `media_compatibility` entries for media which may be written to.
+const WRITABLE_MEDIA_SUFFIXES: &[&str] = &["_r", "_rw", "_re", "_ram", "_r_dl", "_rw_dl", "_mrw_w"];
+
+/// A drive, as described by the `org.freedesktop.UDisks2.Drive` interface.
+///
+/// Diagnostics which UDisks doesn't expose, such as [`link_speed`][Self::link_speed] and
+/// [`cache_size`][Self::cache_size], are read from sysfs on a best effort basis, where the drive
+/// is found by its `serial`.
 #[derive(Clone, Debug, Default)]
 pub struct Drive {
     pub can_power_off: bool,
@@ -34,9 +67,32 @@ pub struct Drive {
     pub wwn: String,
 }
 
+/// How a drive, and the media within it, may be removed from the system.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum DriveKind {
+    /// Neither the drive nor its media are considered removable, such as an internal disk.
+    Fixed,
+    /// The drive itself is removable, but its media is not, such as an external USB disk.
+    Removable,
+    /// The media can be removed while the drive stays attached, such as a card reader or an
+    /// optical drive.
+    MediaReader,
+}
+
+/// Whether a drive spins, normalized from `rotation_rate` by [`Drive::rotation`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Rotation {
+    /// The drive is known not to rotate, such as an SSD or flash drive.
+    SolidState,
+    /// The drive spins at this many revolutions per minute.
+    Spinning(u32),
+    /// Whether the drive rotates, or how fast, isn't known.
+    Unknown,
+}
+
 impl ParseFrom for Drive {
     fn parse_from(path: &str, objects: &DbusObjects) -> Option<Drive> {
-        if let Some(object) = objects.get("org.freedesktop.UDisks2.Drive") {
+        if let Some(object) = objects.get(DEST) {
             let mut drive = Drive::default();
             drive.path = path.to_owned();
             drive.parse(object);
@@ -49,6 +105,251 @@ impl ParseFrom for Drive {
 }
 
 impl Drive {
+    /// Parse a drive from the interfaces of a managed object, as returned by the
+    /// `GetManagedObjects` method of UDisks, for callers which fetch the objects themselves.
+    /// `None` if the object isn't a drive.
+    pub fn from_dbus(path: &str, objects: &DbusObjects) -> Option<Drive> {
+        Drive::parse_from(path, objects)
+    }
+
+    /// Whether the drive has spinning platters (HDD) or not (SSD, flash, etc.), from
+    /// [`rotation`][Self::rotation].
+    ///
+    /// - `Some(true)`: [`Rotation::Spinning`]
+    /// - `Some(false)`: [`Rotation::SolidState`]
+    /// - `None`: [`Rotation::Unknown`]
+    pub fn is_rotational(&self) -> Option<bool> {
+        match self.rotation() {
+            Rotation::SolidState => Some(false),
+            Rotation::Spinning(_) => Some(true),
+            Rotation::Unknown => None,
+        }
+    }
+
+    /// Whether the drive spins, and how fast, normalized from the `rotation_rate` reported by
+    /// UDisks:
+    ///
+    /// - `0` and `1` are [`Rotation::SolidState`]. UDisks reports `0` for non-rotating drives,
+    ///   but `1` is the value ATA drives themselves use for non-rotating media, which some
+    ///   versions passed on as is.
+    /// - from `1025` upwards, the value is the RPM, as [`Rotation::Spinning`]. ATA reserves the
+    ///   values in between.
+    /// - anything else is [`Rotation::Unknown`]. This includes `-1`, which UDisks reports when
+    ///   the kernel flagged the drive as rotational without reporting a rate. That's common for
+    ///   USB bridges and card readers, which claim to be rotational regardless of the media
+    ///   behind them, so it isn't trusted.
+    ///
+    /// ```
+    /// use dbus_udisks2::{Drive, Rotation};
+    ///
+    /// let drive = Drive { rotation_rate: 7200, ..Drive::default() };
+    /// assert_eq!(drive.rotation(), Rotation::Spinning(7200));
+    /// let drive = Drive { rotation_rate: 1, ..Drive::default() };
+    /// assert_eq!(drive.rotation(), Rotation::SolidState);
+    /// ```
+    pub fn rotation(&self) -> Rotation {
+        match self.rotation_rate {
+            0 | 1 => Rotation::SolidState,
+            rate if rate >= 1025 => Rotation::Spinning(rate as u32),
+            _ => Rotation::Unknown,
+        }
+    }
+
+    /// Classify the drive from its `removable` and `media_removable` properties.
+    ///
+    /// - `media_removable` is set: [`DriveKind::MediaReader`]
+    /// - otherwise, `removable` is set: [`DriveKind::Removable`]
+    /// - otherwise: [`DriveKind::Fixed`]
+    ///
+    /// UDisks considers a drive with removable media to be removable too, so `media_removable`
+    /// takes precedence. Note that the kernel reports some USB sticks as having removable media.
+    pub fn kind(&self) -> DriveKind {
+        if self.media_removable {
+            DriveKind::MediaReader
+        } else if self.removable {
+            DriveKind::Removable
+        } else {
+            DriveKind::Fixed
+        }
+    }
+
+    /// Whether the drive sits on a bus which supports hotplugging, and so may be detached while
+    /// the system is running. This is about the drive itself, rather than its media.
+    ///
+    /// True if the drive is on one of the `usb`, `ieee1394`, or `sdio` buses, if UDisks considers
+    /// it removable, or if UDisks is able to power it off, as it only offers that for drives
+    /// which may be safely detached.
+    pub fn is_hotpluggable(&self) -> bool {
+        HOTPLUG_BUSES.contains(&self.connection_bus.as_str())
+            || self.removable
+            || self.can_power_off
+    }
+
+    /// Whether the drive is an optical drive with a disc inserted.
+    pub fn has_optical_media(&self) -> bool {
+        self.optical && self.media_available
+    }
+
+    /// Whether the drive is an optical drive with a blank disc inserted.
+    pub fn has_blank_media(&self) -> bool {
+        self.has_optical_media() && self.optical_blank
+    }
+
+    /// Whether the drive is able to write to the given kind of media, such as
+    /// `"optical_dvd_plus_rw"`.
+    ///
+    /// UDisks names optical media as `optical_` followed by the family (`cd`, `dvd`, `bd`,
+    /// `hddvd`, `mrw`), then a suffix for the recordable variants: `_r` for write-once, `_rw` or
+    /// `_re` for rewritable, `_ram` for DVD-RAM, and `_dl` for dual layer. DVD+ media are
+    /// written with `plus`, as in `optical_dvd_plus_r_dl`. Entries without a suffix, such as
+    /// `optical_cd`, are only readable.
+    ///
+    /// True if `media` is both listed in `media_compatibility` and a recordable variant.
+    pub fn can_write_media(&self, media: &str) -> bool {
+        is_writable_media(media) && self.media_compatibility.iter().any(|m| m == media)
+    }
+
+    /// The recordable kinds of media listed in `media_compatibility`. See
+    /// [`Drive::can_write_media`] for how these are named.
+    pub fn writable_media_types(&self) -> Vec<&str> {
+        self.media_compatibility
+            .iter()
+            .map(String::as_str)
+            .filter(|media| is_writable_media(media))
+            .collect()
+    }
+
+    /// When the drive was first detected, or `None` if unknown.
+    ///
+    /// Converted from `time_detected`, which UDisks reports in **microseconds** since the Unix
+    /// epoch, unlike most other timestamps, such as the seconds of
+    /// [`SmartData::updated`][crate::smart::SmartData::updated].
+    pub fn detected_at(&self) -> Option<SystemTime> {
+        from_usec(self.time_detected)
+    }
+
+    /// When media was last detected in the drive, or `None` if unknown, such as when the drive
+    /// has no media.
+    ///
+    /// Converted from `time_media_detected`, which UDisks reports in **microseconds** since the
+    /// Unix epoch. For drives without removable media, this is the same as `time_detected`.
+    ///
+    /// ```
+    /// # use dbus_udisks2::Drive;
+    /// # use std::time::{Duration, UNIX_EPOCH};
+    /// let drive = Drive {
+    ///     time_media_detected: 1_600_000_000_123_456,
+    ///     ..Drive::default()
+    /// };
+    /// let expected = UNIX_EPOCH + Duration::new(1_600_000_000, 123_456_000);
+    /// assert_eq!(drive.media_detected_at(), Some(expected));
+    /// assert_eq!(Drive::default().media_detected_at(), None);
+    /// ```
+    pub fn media_detected_at(&self) -> Option<SystemTime> {
+        from_usec(self.time_media_detected)
+    }
+
+    /// The `model` with whitespace trimmed and runs of it collapsed to single spaces, as UDisks
+    /// passes it through from the device, often padded.
+    pub fn clean_model(&self) -> String {
+        collapse_whitespace(&self.model)
+    }
+
+    /// The `vendor` with whitespace trimmed and collapsed, like
+    /// [`clean_model`][Self::clean_model].
+    pub fn clean_vendor(&self) -> String {
+        collapse_whitespace(&self.vendor)
+    }
+
+    /// The firmware revision from `revision`, with whitespace trimmed and collapsed, like
+    /// [`clean_model`][Self::clean_model].
+    pub fn firmware(&self) -> String {
+        collapse_whitespace(&self.revision)
+    }
+
+    /// The size of the drive's cache in bytes, as reported by ATA drives, and shown by
+    /// `hdparm -I`. UDisks doesn't expose this, so it is read from sysfs on a best effort basis:
+    /// from the IDENTIFY DEVICE data of the device in `/sys/class/ata_device/*/id` with the same
+    /// serial.
+    ///
+    /// `None` if the drive isn't attached through libata, which is the case for NVMe, USB, and
+    /// SCSI drives, as the kernel doesn't read their cache size. Also `None` if an ATA drive
+    /// leaves the size unset, as recent drives may, the field being obsolete since ATA-4.
+    pub fn cache_size(&self) -> Option<u64> {
+        self.ata_device()
+            .and_then(|(_, words)| ata_cache_size(&words))
+    }
+
+    /// The negotiated speed of the link to the drive, such as to spot a SATA SSD running at
+    /// 3 Gbps due to a bad cable. UDisks doesn't expose this, so it is read from sysfs on a best
+    /// effort basis, from the first device with the same serial among:
+    ///
+    /// - `/sys/class/ata_device`, by the serial in its IDENTIFY DEVICE data, for the
+    ///   `sata_spd` of its `ata_link`
+    /// - `/sys/class/nvme`, for the PCIe link of the controller
+    /// - `/sys/bus/usb/devices`, for the `speed` of the USB device
+    ///
+    /// `None` if the drive has no serial or isn't found, such as for SCSI drives, or USB drives
+    /// whose bridge reports the serial of the disk behind it, or if the link is down. For a
+    /// block, see [`Block::link_speed`][crate::Block::link_speed], which finds the device
+    /// through its device number instead.
+    pub fn link_speed(&self) -> Option<LinkSpeed> {
+        let device = self
+            .ata_device()
+            .map(|(path, _)| path)
+            .or_else(|| find_by_serial(NVME_CONTROLLERS, &self.serial))
+            .or_else(|| find_by_serial(USB_DEVICES, &self.serial))?;
+        crate::link::link_speed(&device)
+    }
+
+    /// The libata device with the same serial, along with its IDENTIFY DEVICE data.
+    fn ata_device(&self) -> Option<(PathBuf, Vec<u16>)> {
+        if self.serial.is_empty() {
+            return None;
+        }
+        fs::read_dir(ATA_DEVICES)
+            .ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let id = fs::read_to_string(entry.path().join("id")).ok()?;
+                Some((entry.path(), ata_identify(&id)?))
+            })
+            .find(|(_, words)| ata_serial(words).as_ref() == Some(&self.serial))
+    }
+
+    /// A human-readable name for the drive, built from its `vendor` and `model`.
+    ///
+    /// The vendor is omitted when it is empty or the model already begins with it, as UDisks
+    /// often passes through models such as `"Samsung SSD 860"` with a vendor of `"Samsung"`.
+    /// Whitespace is trimmed and collapsed. Falls back to `id` if neither are known.
+    pub fn display_name(&self) -> String {
+        let vendor = self.vendor.split_whitespace().collect::<Vec<_>>();
+        let model = self.model.split_whitespace().collect::<Vec<_>>();
+
+        let model_has_vendor = model.len() >= vendor.len()
+            && vendor
+                .iter()
+                .zip(&model)
+                .all(|(v, m)| v.eq_ignore_ascii_case(m));
+
+        let name = if model_has_vendor {
+            model.join(" ")
+        } else {
+            vendor
+                .iter()
+                .chain(&model)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        if name.is_empty() {
+            self.id.clone()
+        } else {
+            name
+        }
+    }
+
     fn parse(&mut self, objects: &KeyVariant) {
         for (key, ref value) in objects {
             match key.as_str() {
@@ -72,7 +373,7 @@ impl Drive {
                 "OpticalNumSessions" => self.optical_num_sessions = get_u64(value),
                 "Removable" => self.removable = get_bool(value),
                 "Revision" => self.revision = get_string(value).unwrap_or_default(),
-                "RotationRate" => self.rotation_rate = get_u64(value) as i64,
+                "RotationRate" => self.rotation_rate = get_i64(value),
                 "Seat" => self.seat = get_string(value).unwrap_or_default(),
                 "Serial" => self.serial = get_string(value).unwrap_or_default(),
                 "SiblingId" => self.sibling_id = get_string(value).unwrap_or_default(),
@@ -83,16 +384,143 @@ impl Drive {
                 "Vendor" => self.vendor = get_string(value).unwrap_or_default(),
                 "WWN" => self.wwn = get_string(value).unwrap_or_default(),
                 _ => {
-                    #[cfg(debug_assertions)]
-                    eprintln!("unhandled org.freedesktop.UDisks2.Drive::{}", key);
+                    log::debug!("unhandled org.freedesktop.UDisks2.Drive::{}", key);
                 }
             }
         }
     }
 }
 
+fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parse the IDENTIFY DEVICE data of an ATA device, written by libata as hex words.
+fn ata_identify(id: &str) -> Option<Vec<u16>> {
+    id.split_whitespace()
+        .map(|word| u16::from_str_radix(word, 16).ok())
+        .collect()
+}
+
+/// The serial number from IDENTIFY DEVICE data, as UDisks reports it.
+fn ata_serial(words: &[u16]) -> Option<String> {
+    let bytes = words
+        .get(ATA_SERIAL)?
+        .iter()
+        .flat_map(|word| word.to_be_bytes())
+        .collect::<Vec<u8>>();
+    Some(udev_serial(&String::from_utf8_lossy(&bytes)))
+}
+
+/// A serial with runs of whitespace trimmed and replaced by `_`, as udev does for the
+/// `ID_SERIAL_SHORT` which UDisks reports as `serial`.
+fn udev_serial(serial: &str) -> String {
+    serial.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// The device in the given sysfs directory whose `serial` is the given one. Devices without a
+/// `serial` are skipped.
+fn find_by_serial(dir: &str, serial: &str) -> Option<PathBuf> {
+    if serial.is_empty() {
+        return None;
+    }
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| match fs::read_to_string(path.join("serial")) {
+            Ok(found) => udev_serial(&found) == serial,
+            Err(_) => false,
+        })
+}
+
+fn ata_cache_size(words: &[u16]) -> Option<u64> {
+    match *words.get(ATA_BUFFER_SIZE)? {
+        0 | 0xffff => None,
+        sectors => Some(u64::from(sectors) * 512),
+    }
+}
+
+fn from_usec(usec: u64) -> Option<SystemTime> {
+    if usec == 0 {
+        None
+    } else {
+        Some(UNIX_EPOCH + Duration::from_micros(usec))
+    }
+}
+
+fn is_writable_media(media: &str) -> bool {
+    media.starts_with("optical_")
+        && WRITABLE_MEDIA_SUFFIXES
+            .iter()
+            .any(|suffix| media.ends_with(suffix))
+}
+
 impl<'a> From<&'a Drive> for dbus::Path<'a> {
     fn from(drive: &'a Drive) -> Self {
         (&drive.path).into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbus::arg::Variant;
+
+    /// Format IDENTIFY DEVICE data as libata does, eight words to a line.
+    fn identify(words: &[u16]) -> String {
+        words
+            .chunks(8)
+            .map(|line| {
+                let line = line.iter().map(|word| format!("{:04x}", word));
+                line.collect::<Vec<_>>().join(" ") + "\n"
+            })
+            .collect()
+    }
+
+    /// Parse a drive with the given `RotationRate`, sent as an `int32` like UDisks does.
+    fn with_rotation_rate(rate: i32) -> Drive {
+        let mut drive = KeyVariant::new();
+        drive.insert("RotationRate".into(), Variant(Box::new(rate)));
+        let mut objects = DbusObjects::new();
+        objects.insert(DEST.into(), drive);
+        Drive::from_dbus("/org/freedesktop/UDisks2/drives/Mock", &objects).unwrap()
+    }
+
+    #[test]
+    fn parses_rotation_rate() {
+        let drive = with_rotation_rate(7200);
+        assert_eq!(drive.rotation_rate, 7200);
+        assert_eq!(drive.rotation(), Rotation::Spinning(7200));
+        assert_eq!(with_rotation_rate(-1).rotation(), Rotation::Unknown);
+        assert_eq!(with_rotation_rate(0).rotation(), Rotation::SolidState);
+    }
+
+    #[test]
+    fn parses_rotational() {
+        assert_eq!(with_rotation_rate(5400).is_rotational(), Some(true));
+        assert_eq!(with_rotation_rate(0).is_rotational(), Some(false));
+        assert_eq!(with_rotation_rate(-1).is_rotational(), None);
+    }
+
+    #[test]
+    fn reads_ata_identify() {
+        let mut words = [0u16; 256];
+        // The serial is right-justified, with its characters swapped within each word.
+        for (word, pair) in words[ATA_SERIAL]
+            .iter_mut()
+            .zip(b"     WD-WCC4N1234567".chunks(2))
+        {
+            *word = u16::from_be_bytes([pair[0], pair[1]]);
+        }
+        words[ATA_BUFFER_SIZE] = 0x4000;
+
+        let words = ata_identify(&identify(&words)).unwrap();
+        assert_eq!(words.len(), 256);
+        assert_eq!(ata_serial(&words).as_deref(), Some("WD-WCC4N1234567"));
+        assert_eq!(ata_cache_size(&words), Some(8 * 1024 * 1024));
+        assert_eq!(ata_cache_size(&[0; 256]), None);
+        // The serial of an NVMe controller, padded by the kernel.
+        assert_eq!(udev_serial("S4EVNS0N 123456     \n"), "S4EVNS0N_123456");
+    }
+}
@@ -0,0 +1,6 @@
+//! Constants related to encrypted blocks.
+
+pub(crate) const DEST: &str = "org.freedesktop.UDisks2.Encrypted";
+pub(crate) const LOCK: &str = "Lock";
+pub(crate) const PASSPHRASE: &str = "passphrase";
+pub(crate) const CLEARTEXT_DEVICE: &str = "CleartextDevice";
@@ -0,0 +1,95 @@
+use std::error::Error;
+use std::fmt;
+
+/// A [`Result`][std::result::Result] whose error defaults to [`UdisksError`].
+pub type Result<T, E = UdisksError> = std::result::Result<T, E>;
+
+/// Errors which may occur when performing operations through UDisks.
+#[derive(Debug)]
+pub enum UdisksError {
+    /// The D-Bus call failed, or UDisks returned an error.
+    DBus(dbus::Error),
+    /// The operation requires a partition, but the block at this path is not one.
+    NotAPartition(String),
+    /// The operation can't be performed on an extended partition, such as the one at this path,
+    /// as it holds other partitions rather than a file system.
+    ExtendedPartition(String),
+    /// The operation requires a loop device, but the block at this path is not one.
+    NotALoopDevice(String),
+    /// The operation requires an encrypted block, but the block at this path is not one.
+    NotEncrypted(String),
+    /// The operation requires an unlocked encrypted block, but the block at this path is locked.
+    Locked(String),
+    /// The drive at this path can't be ejected.
+    NotEjectable(String),
+    /// There is no block at this path.
+    NoSuchBlock(String),
+    /// There is no drive at this path, such as after it was unplugged.
+    NoSuchDrive(String),
+    /// The operation requires a partition table, but the block at this path has none.
+    NoPartitionTable(String),
+    /// The partition table of the block at this path has no room for another partition.
+    PartitionTableFull(String),
+    /// The object at this path did not reach the expected state in time.
+    TimedOut(String),
+    /// The region requested for a new partition in the block at this path holds no space once
+    /// aligned.
+    RegionTooSmall(String),
+    /// The encrypted block at this path can't be shrunk safely, as UDisks doesn't report the size
+    /// of its LUKS header, as older versions don't.
+    UnknownMetadataSize(String),
+    /// The size requested for the encrypted block at this path leaves no room for its contents
+    /// after the LUKS header.
+    SizeTooSmall(String),
+}
+
+impl fmt::Display for UdisksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UdisksError::DBus(why) => write!(f, "D-Bus call failed: {}", why),
+            UdisksError::NotAPartition(path) => write!(f, "{} is not a partition", path),
+            UdisksError::ExtendedPartition(path) => {
+                write!(f, "{} is an extended partition", path)
+            }
+            UdisksError::NotALoopDevice(path) => write!(f, "{} is not a loop device", path),
+            UdisksError::NotEncrypted(path) => write!(f, "{} is not encrypted", path),
+            UdisksError::Locked(path) => write!(f, "{} is locked", path),
+            UdisksError::NotEjectable(path) => write!(f, "{} can't be ejected", path),
+            UdisksError::NoSuchBlock(path) => write!(f, "no block at {}", path),
+            UdisksError::NoSuchDrive(path) => write!(f, "no drive at {}", path),
+            UdisksError::NoPartitionTable(path) => write!(f, "{} has no partition table", path),
+            UdisksError::PartitionTableFull(path) => {
+                write!(f, "the partition table of {} is full", path)
+            }
+            UdisksError::TimedOut(path) => write!(f, "timed out waiting on {}", path),
+            UdisksError::RegionTooSmall(path) => {
+                write!(f, "no aligned space in the region requested on {}", path)
+            }
+            UdisksError::UnknownMetadataSize(path) => {
+                write!(f, "the size of the LUKS header of {} is unknown", path)
+            }
+            UdisksError::SizeTooSmall(path) => {
+                write!(
+                    f,
+                    "the size requested for {} leaves no room after its header",
+                    path
+                )
+            }
+        }
+    }
+}
+
+impl Error for UdisksError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UdisksError::DBus(why) => Some(why),
+            _ => None,
+        }
+    }
+}
+
+impl From<dbus::Error> for UdisksError {
+    fn from(why: dbus::Error) -> Self {
+        UdisksError::DBus(why)
+    }
+}
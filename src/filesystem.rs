@@ -0,0 +1,154 @@
+//! Types related to the file systems of blocks.
+
+use crate::options::{Interactivity, NO_USER_INTERACTION};
+use crate::utils::*;
+use dbus::arg::Variant;
+use std::fmt;
+
+pub(crate) const DEST: &str = "org.freedesktop.UDisks2.Filesystem";
+pub(crate) const MOUNT: &str = "Mount";
+pub(crate) const UNMOUNT: &str = "Unmount";
+pub(crate) const MOUNT_POINTS: &str = "MountPoints";
+const FORCE: &str = "force";
+const FSTYPE: &str = "fstype";
+const OPTIONS: &str = "options";
+
+/// Bits of the resize mode returned by `CanResize`, from libblockdev's `BDFSResizeFlags`.
+const OFFLINE_SHRINK: u64 = 1 << 1;
+const OFFLINE_GROW: u64 = 1 << 2;
+const ONLINE_SHRINK: u64 = 1 << 3;
+const ONLINE_GROW: u64 = 1 << 4;
+
+/// The operations which UDisks is able to perform on a type of file system, as returned by
+/// [`filesystem_capabilities`][crate::UDisks2::filesystem_capabilities]. Each depends on the
+/// tools for the file system being installed, such as `mkfs.xfs` or `xfs_growfs`.
+///
+/// UDisks has no way to query whether a label can be set, so that is not included.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct FsCapabilities {
+    pub can_create: bool,
+    pub can_check: bool,
+    pub can_repair: bool,
+    /// Whether the file system may be grown while unmounted.
+    pub can_grow_offline: bool,
+    /// Whether the file system may be grown while mounted.
+    pub can_grow_online: bool,
+    /// Whether the file system may be shrunk while unmounted.
+    pub can_shrink_offline: bool,
+    /// Whether the file system may be shrunk while mounted. Few file systems support this, and
+    /// some, such as XFS, can't be shrunk at all.
+    pub can_shrink_online: bool,
+}
+
+impl FsCapabilities {
+    /// Build from the availability returned by each of the manager's `Can*` methods, and the
+    /// mode returned by `CanResize`.
+    pub(crate) fn new(create: bool, check: bool, repair: bool, resize: Option<u64>) -> Self {
+        let mode = resize.unwrap_or(0);
+        FsCapabilities {
+            can_create: create,
+            can_check: check,
+            can_repair: repair,
+            can_grow_offline: mode & OFFLINE_GROW != 0,
+            can_grow_online: mode & ONLINE_GROW != 0,
+            can_shrink_offline: mode & OFFLINE_SHRINK != 0,
+            can_shrink_online: mode & ONLINE_SHRINK != 0,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+/// Options for [`mount`][crate::UDisks2::mount] ([async version][crate::AsyncUDisks2::mount]).
+pub struct MountOptions {
+    /// The file system type to mount as, instead of the type probed by UDisks.
+    pub fstype: Option<String>,
+    /// Mount options, such as `"ro"` or `"noexec"`. UDisks only permits options from its
+    /// allow-list for the file system type, and fails the mount otherwise.
+    ///
+    /// These are joined with commas when passed to UDisks, so each must be a single option.
+    pub options: Vec<String>,
+    /// Additional options passed to UDisks as-is, for options not modeled by this struct. Keys
+    /// must exactly match the option names of `org.freedesktop.UDisks2.Filesystem.Mount`, and
+    /// take precedence over the fields above.
+    pub extra: KeyVariant,
+}
+
+impl MountOptions {
+    /// Add a mount option, such as `"ro"` or `"noatime"`.
+    ///
+    /// ```
+    /// use dbus_udisks2::filesystem::MountOptions;
+    ///
+    /// let options = MountOptions::default()
+    ///     .option("ro")
+    ///     .option("noatime")
+    ///     .option_value("uid", 1000);
+    /// assert_eq!(options.options, ["ro", "noatime", "uid=1000"]);
+    /// ```
+    pub fn option(mut self, option: impl Into<String>) -> Self {
+        self.options.push(option.into());
+        self
+    }
+
+    /// Add a mount option with a value, as `key=value`, such as `uid=1000`.
+    pub fn option_value(self, key: &str, value: impl fmt::Display) -> Self {
+        self.option(format!("{}={}", key, value))
+    }
+
+    /// Add the mount options from a comma-separated string, such as `"ro,noatime"`, as they
+    /// would be passed to `mount -o`. Empty options are skipped.
+    pub fn options_str(mut self, options: &str) -> Self {
+        self.options.extend(
+            options
+                .split(',')
+                .filter(|option| !option.is_empty())
+                .map(String::from),
+        );
+        self
+    }
+
+    pub(crate) fn build(&self, interactive: Interactivity) -> KeyVariant<&str> {
+        let mut options = KeyVariant::<&str>::new();
+        if let Some(ref fstype) = self.fstype {
+            options.insert(FSTYPE, Variant(Box::new(fstype.clone())));
+        }
+        if !self.options.is_empty() {
+            options.insert(OPTIONS, Variant(Box::new(self.options.join(","))));
+        }
+        if let Some(value) = interactive.no_user_interaction() {
+            options.insert(NO_USER_INTERACTION, Variant(Box::new(value)));
+        }
+        merge_extra(&mut options, &self.extra);
+        options
+    }
+}
+
+#[derive(Debug, Default)]
+/// Options for [`unmount`][crate::UDisks2::unmount]
+/// ([async version][crate::AsyncUDisks2::unmount]).
+pub struct UnmountOptions {
+    /// Unmount the file system even if it is busy.
+    ///
+    /// UDisks implements this as a lazy unmount (`umount -l`, or `MNT_DETACH`): the file system
+    /// is detached from the hierarchy immediately, and cleaned up once it is no longer busy. Open
+    /// files remain usable until they are closed. Without it, unmounting a busy file system fails.
+    pub force: bool,
+    /// Additional options passed to UDisks as-is, for options not modeled by this struct. Keys
+    /// must exactly match the option names of `org.freedesktop.UDisks2.Filesystem.Unmount`, and
+    /// take precedence over the fields above.
+    pub extra: KeyVariant,
+}
+
+impl UnmountOptions {
+    pub(crate) fn build(&self, interactive: Interactivity) -> KeyVariant<&str> {
+        let mut options = KeyVariant::<&str>::new();
+        if self.force {
+            options.insert(FORCE, Variant(Box::new(true)));
+        }
+        if let Some(value) = interactive.no_user_interaction() {
+            options.insert(NO_USER_INTERACTION, Variant(Box::new(value)));
+        }
+        merge_extra(&mut options, &self.extra);
+        options
+    }
+}
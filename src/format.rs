@@ -0,0 +1,157 @@
+//! Types related to formatting blocks.
+
+use crate::options::{Interactivity, NO_USER_INTERACTION};
+use crate::utils::*;
+use crate::BlockConfigurationFstab;
+use dbus::arg::Variant;
+
+pub(crate) const DEST: &str = "org.freedesktop.UDisks2.Block";
+pub(crate) const FORMAT: &str = "Format";
+const LABEL: &str = "label";
+const TAKE_OWNERSHIP: &str = "take-ownership";
+const ERASE: &str = "erase";
+pub(crate) const UPDATE_PARTITION_TYPE: &str = "update-partition-type";
+const NO_DISCARD: &str = "no-discard";
+const TEAR_DOWN: &str = "tear-down";
+const NO_BLOCK: &str = "no-block";
+const DRY_RUN_FIRST: &str = "dry-run-first";
+const MKFS_ARGS: &str = "mkfs-args";
+const CONFIG_ITEMS: &str = "config-items";
+const ENCRYPT_TYPE: &str = "encrypt.type";
+pub(crate) const ENCRYPT_PASSPHRASE: &str = "encrypt.passphrase";
+
+#[derive(Debug, Default)]
+/// Options for [`format`][crate::UDisks2::format]
+/// ([async version][crate::AsyncUDisks2::format]).
+pub struct FormatOptions {
+    /// The label of the new file system.
+    pub label: Option<String>,
+    /// Change the ownership of the new file system to the calling user. Only supported by file
+    /// systems with Unix permissions, such as ext4 and xfs. For file systems without them, such
+    /// as vfat, exfat, and ntfs, ownership is instead decided at mount time by the `uid=` and
+    /// `gid=` mount options, which may be set with [`fstab`][Self::fstab].
+    pub take_ownership: bool,
+    /// Erase the existing contents before formatting: `"zero"` writes zeroes over the device,
+    /// and `"ata-secure-erase"` or `"ata-secure-erase-enhanced"` perform an ATA secure erase.
+    pub erase: Option<String>,
+    /// Change the partition type to match the new file system, if the block is a partition.
+    pub update_partition_type: bool,
+    /// Skip discarding the blocks of the device before formatting.
+    pub no_discard: bool,
+    /// Remove any fstab and crypttab configuration of the block and its children.
+    pub tear_down: bool,
+    /// Return as soon as the file system is being created, instead of waiting for `mkfs` to
+    /// finish and for the kernel to probe the new contents of the block. The running job may
+    /// then be found with [`jobs_of`][crate::UDisks2::jobs_of], and stopped with
+    /// [`cancel_job`][crate::UDisks2::cancel_job].
+    ///
+    /// UDisks has no option to prevent the kernel from rescanning the block for partitions
+    /// after formatting. For a loop device, whether its partitions are scanned is decided when
+    /// it's set up, by the `no-part-scan` option of `org.freedesktop.UDisks2.Manager.LoopSetup`,
+    /// so an image intended for another tool should be set up with partition scanning disabled.
+    pub no_block: bool,
+    /// Run `mkfs` in its dry-run mode before formatting, so that invalid options, such as a
+    /// label which is too long, fail before the device is touched.
+    pub dry_run_first: bool,
+    /// Additional arguments passed as-is to the `mkfs` tool of the file system. Requires
+    /// UDisks 2.9 or later.
+    pub mkfs_args: Vec<String>,
+    /// An fstab entry added for the new file system as part of the same operation, such as
+    /// with the `opts` of `"uid=1000,gid=1000"` to have a vfat or exfat file system owned by
+    /// that user once mounted. As the UUID of the file system is not known beforehand, `fsname`
+    /// should refer to it by a label or the device.
+    pub fstab: Option<BlockConfigurationFstab>,
+    /// The type of encryption to use when formatting with encryption, such as `"luks1"` or
+    /// `"luks2"`. Defaults to the system default, which is given by
+    /// [`default_encryption_type`][crate::UDisks2::default_encryption_type].
+    pub encrypt_type: Option<String>,
+    /// Additional options passed to UDisks as-is, for options not modeled by this struct. Keys
+    /// must exactly match the option names of `org.freedesktop.UDisks2.Block.Format`, and take
+    /// precedence over the fields above.
+    pub extra: KeyVariant,
+}
+
+impl FormatOptions {
+    pub(crate) fn build(&self, interactive: Interactivity) -> KeyVariant<&str> {
+        let mut options = KeyVariant::<&str>::new();
+        if let Some(ref label) = self.label {
+            options.insert(LABEL, Variant(Box::new(label.clone())));
+        }
+        if self.take_ownership {
+            options.insert(TAKE_OWNERSHIP, Variant(Box::new(true)));
+        }
+        if let Some(ref erase) = self.erase {
+            options.insert(ERASE, Variant(Box::new(erase.clone())));
+        }
+        if self.update_partition_type {
+            options.insert(UPDATE_PARTITION_TYPE, Variant(Box::new(true)));
+        }
+        if self.no_discard {
+            options.insert(NO_DISCARD, Variant(Box::new(true)));
+        }
+        if self.tear_down {
+            options.insert(TEAR_DOWN, Variant(Box::new(true)));
+        }
+        if self.no_block {
+            options.insert(NO_BLOCK, Variant(Box::new(true)));
+        }
+        if self.dry_run_first {
+            options.insert(DRY_RUN_FIRST, Variant(Box::new(true)));
+        }
+        if !self.mkfs_args.is_empty() {
+            options.insert(MKFS_ARGS, Variant(Box::new(self.mkfs_args.clone())));
+        }
+        if let Some(ref fstab) = self.fstab {
+            let items = vec![(String::from("fstab"), fstab.to_dbus())];
+            options.insert(CONFIG_ITEMS, Variant(Box::new(items)));
+        }
+        if let Some(ref encrypt_type) = self.encrypt_type {
+            options.insert(ENCRYPT_TYPE, Variant(Box::new(encrypt_type.clone())));
+        }
+        if let Some(value) = interactive.no_user_interaction() {
+            options.insert(NO_USER_INTERACTION, Variant(Box::new(value)));
+        }
+        merge_extra(&mut options, &self.extra);
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbus::arg::RefArg;
+
+    #[test]
+    fn builds_options_for_loop_image() {
+        let options = FormatOptions {
+            label: Some(String::from("image")),
+            no_discard: true,
+            tear_down: true,
+            no_block: true,
+            ..Default::default()
+        };
+        let options = options.build(Interactivity::NonInteractive);
+
+        let mut keys = options.keys().copied().collect::<Vec<_>>();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            [
+                "auth.no_user_interaction",
+                "label",
+                "no-block",
+                "no-discard",
+                "tear-down"
+            ]
+        );
+        assert_eq!(options["label"].0.as_str(), Some("image"));
+        for key in &[
+            "no-block",
+            "no-discard",
+            "tear-down",
+            "auth.no_user_interaction",
+        ] {
+            assert_eq!(options[key].0.as_u64(), Some(1), "{}", key);
+        }
+    }
+}
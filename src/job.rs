@@ -0,0 +1,76 @@
+use crate::utils::*;
+use crate::DbusObjects;
+
+pub(crate) const DEST: &str = "org.freedesktop.UDisks2.Job";
+pub(crate) const CANCEL: &str = "Cancel";
+
+/// A long-running operation on the UDisks server, such as formatting or erasing a block.
+///
+/// Jobs are objects of their own, under `/org/freedesktop/UDisks2/jobs/`, which only exist
+/// while the operation runs. They're found by refreshing the cache while the operation is in
+/// progress, which is typically only possible for methods called with the `no_block` option.
+#[derive(Clone, Debug, Default)]
+pub struct Job {
+    pub path: String,
+    /// The kind of operation, such as `"format-mkfs"` or `"ata-secure-erase"`.
+    pub operation: String,
+    /// Progress from `0.0` to `1.0`, only meaningful if `progress_valid`.
+    pub progress: f64,
+    pub progress_valid: bool,
+    /// The number of bytes being processed, or `0` if unknown.
+    pub bytes: u64,
+    /// Bytes processed per second, or `0` if unknown.
+    pub rate: u64,
+    /// Microseconds since the epoch.
+    pub start_time: u64,
+    /// Microseconds since the epoch, or `0` if unknown.
+    pub expected_end_time: u64,
+    /// Paths of the objects affected by the job.
+    pub objects: Vec<String>,
+    pub started_by_uid: u64,
+    pub cancelable: bool,
+}
+
+impl ParseFrom for Job {
+    fn parse_from(path: &str, objects: &DbusObjects) -> Option<Job> {
+        if let Some(object) = objects.get(DEST) {
+            let mut job = Job {
+                path: path.to_owned(),
+                ..Default::default()
+            };
+            job.parse(object);
+
+            Some(job)
+        } else {
+            None
+        }
+    }
+}
+
+impl Job {
+    fn parse(&mut self, objects: &KeyVariant) {
+        for (key, value) in objects {
+            match key.as_str() {
+                "Operation" => self.operation = get_string(value).unwrap_or_default(),
+                "Progress" => self.progress = get_f64(value),
+                "ProgressValid" => self.progress_valid = get_bool(value),
+                "Bytes" => self.bytes = get_u64(value),
+                "Rate" => self.rate = get_u64(value),
+                "StartTime" => self.start_time = get_u64(value),
+                "ExpectedEndTime" => self.expected_end_time = get_u64(value),
+                "Objects" => self.objects = get_string_array(value).unwrap_or_default(),
+                "StartedByUID" => self.started_by_uid = get_u64(value),
+                "Cancelable" => self.cancelable = get_bool(value),
+                _ => {
+                    log::debug!("unhandled org.freedesktop.UDisks2.Job::{}", key);
+                }
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a Job> for dbus::Path<'a> {
+    fn from(job: &'a Job) -> Self {
+        (&job.path).into()
+    }
+}
@@ -1,48 +1,125 @@
 //! You probably want to look at [`UDisks2`] or [`AsyncUDisks2`].
 
-use std::collections::HashMap;
-use std::ops::Deref;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use dbus::arg::Variant;
 use dbus::blocking;
 use dbus::blocking::stdintf::org_freedesktop_dbus::{ObjectManager, Properties};
+use dbus::channel::Sender;
+use dbus::Message;
 
+use crate::filesystem::{FsCapabilities, MountOptions, UnmountOptions};
+use crate::format::FormatOptions;
+use crate::options::{options_builder, Interactivity, Wakeup};
+use crate::partition::PartitionOptions;
+use crate::power::PowerState;
 use crate::smart::{RawSmartAttribute, SmartData, SmartStatus, SmartValue};
 pub use block::*;
+/// Re-exported so that the error type may be named without depending on `dbus` directly.
+pub use dbus::Error;
 pub use disks::*;
 pub use drive::*;
+pub use error::{Result, UdisksError};
+pub use job::*;
+pub use link::LinkSpeed;
+pub use modules::*;
 #[cfg(feature = "futures")]
 pub use nonblock::*;
+pub use polkit::UdisksAction;
+pub use spec::DeviceSpec;
+pub use utils::DbusObjects;
 use utils::*;
 
 mod block;
 mod disks;
 mod drive;
+mod encrypted;
+mod error;
+pub mod filesystem;
+pub mod format;
+mod job;
+mod link;
+mod loop_device;
+mod manager;
+mod modules;
 #[cfg(feature = "futures")]
 mod nonblock;
+pub mod options;
+pub mod partition;
+mod polkit;
+pub mod power;
 pub mod smart;
+mod spec;
 mod utils;
 
 const DEST: &str = "org.freedesktop.UDisks2";
 const PATH: &str = "/org/freedesktop/UDisks2";
-const NO_WAKEUP: &str = "nowakeup";
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(3000);
+const SIZE: &str = "Size";
+/// The method which resizes partitions, LUKS containers, and file systems alike.
+const RESIZE: &str = "Resize";
+pub(crate) const BLOCK: &str = "org.freedesktop.UDisks2.Block";
+const LOGICAL_VOLUME: &str = "org.freedesktop.UDisks2.LogicalVolume";
+const PHYSICAL_VOLUME: &str = "org.freedesktop.UDisks2.PhysicalVolume";
+const VOLUME_GROUP: &str = "VolumeGroup";
+const BLOCK_DEVICE: &str = "BlockDevice";
+const DEACTIVATE: &str = "Deactivate";
+/// The interfaces of managed objects which are parsed, or otherwise used, by this crate.
+const HANDLED_INTERFACES: &[&str] = &[
+    BLOCK,
+    LOGICAL_VOLUME,
+    PHYSICAL_VOLUME,
+    drive::DEST,
+    encrypted::DEST,
+    filesystem::DEST,
+    job::DEST,
+    loop_device::DEST,
+    manager::DEST,
+    modules::BCACHE,
+    modules::ZRAM,
+    partition::DEST,
+    partition::TABLE_DEST,
+    smart::DEST,
+    "org.freedesktop.UDisks2.Block.LVM2",
+    "org.freedesktop.UDisks2.Swapspace",
+];
+const SIZED_INTERFACES: &[&str] = &[
+    "org.freedesktop.UDisks2.Block",
+    "org.freedesktop.UDisks2.Partition",
+];
 
 #[derive(Default)]
 struct DiskCache(HashMap<dbus::Path<'static>, DbusObjects>);
 
 impl DiskCache {
+    /// The cached interfaces of the object at the given dbus object path.
+    fn object(&self, path: &str) -> Option<&DbusObjects> {
+        self.0.get(&dbus::Path::new(path).ok()?)
+    }
+
+    fn object_mut(&mut self, path: &str) -> Option<&mut DbusObjects> {
+        self.0.get_mut(&dbus::Path::new(path).ok()?)
+    }
+
     fn get_object<T: ParseFrom>(&self, path: &str) -> Option<T> {
-        self.0
-            .iter()
-            .flat_map(|object| {
-                if object.0.deref() == path {
-                    T::parse_from(&object.0, &object.1)
-                } else {
-                    None
-                }
-            })
-            .next()
+        T::parse_from(path, self.object(path)?)
+    }
+
+    /// Whether the object at the given dbus object path implements the interface.
+    fn has_interface(&self, path: &str, interface: &str) -> bool {
+        matches!(self.object(path), Some(object) if object.contains_key(interface))
+    }
+
+    /// Other drives which share the non-empty `sibling_id` of the given drive.
+    fn sibling_drives(&self, drive: &Drive) -> Vec<Drive> {
+        if drive.sibling_id.is_empty() {
+            return Vec::new();
+        }
+        self.get_drives()
+            .filter(|other| other.sibling_id == drive.sibling_id && other.path != drive.path)
+            .collect()
     }
 
     /// Find the drive that corresponds to the given dbus object path.
@@ -57,9 +134,38 @@ impl DiskCache {
             .flat_map(|object| Drive::parse_from(&object.0, &object.1))
     }
 
+    /// An iterator of `Bcache` objects fetched from the inner cached managed objects.
+    fn get_bcache_devices<'a>(&'a self) -> impl Iterator<Item = Bcache> + 'a {
+        self.0
+            .iter()
+            .flat_map(|(path, object)| Bcache::parse_from(path, object))
+    }
+
+    /// An iterator of `Zram` objects fetched from the inner cached managed objects.
+    fn get_zram_devices<'a>(&'a self) -> impl Iterator<Item = Zram> + 'a {
+        self.0
+            .iter()
+            .flat_map(|(path, object)| Zram::parse_from(path, object))
+    }
+
+    /// An iterator of `Job` objects fetched from the inner cached managed objects.
+    fn get_jobs<'a>(&'a self) -> impl Iterator<Item = Job> + 'a {
+        self.0
+            .iter()
+            .flat_map(|(path, object)| Job::parse_from(path, object))
+    }
+
+    /// The running jobs which affect the object at the given dbus object path.
+    fn jobs_of(&self, path: &str) -> Vec<Job> {
+        self.get_jobs()
+            .filter(|job| job.objects.iter().any(|object| object == path))
+            .collect()
+    }
+
     /// Find the block that corresponds to the given dbus object path.
     fn get_block(&self, path: &str) -> Option<Block> {
         self.get_object::<Block>(path)
+            .map(|block| self.with_logical_volume(block))
     }
 
     /// An iterator of `Block` objects fetched from the inner cached managed objects.
@@ -67,22 +173,397 @@ impl DiskCache {
         self.0
             .iter()
             .flat_map(|object| Block::parse_from(&object.0, &object.1))
+            .map(move |block| self.with_logical_volume(block))
+    }
+
+    /// Resolve the logical volume of a block, which is an object of its own.
+    fn with_logical_volume(&self, mut block: Block) -> Block {
+        if let Some(ref mut lv) = block.logical_volume {
+            let object = self
+                .object(&lv.path)
+                .and_then(|object| object.get(LOGICAL_VOLUME));
+            if let Some(object) = object {
+                lv.parse(object);
+            }
+        }
+        block
+    }
+
+    /// Find the block which is mounted at the given path.
+    fn block_for_mount(&self, path: &Path) -> Option<Block> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let blocks = self.get_blocks().collect::<Vec<_>>();
+        if let Some(block) = blocks.iter().find(|b| b.mount_points.contains(&path)) {
+            return Some(block.clone());
+        }
+        blocks.into_iter().find(|block| {
+            block
+                .mount_points
+                .iter()
+                .any(|mount| matches!(mount.canonicalize(), Ok(mount) if mount == path))
+        })
+    }
+
+    /// Find the block with the given device node, preferred device node, or symlink.
+    fn get_block_by_device(&self, device: &Path) -> Option<Block> {
+        self.get_blocks().find(|block| block.matches_device(device))
+    }
+
+    /// The interfaces of every cached object which are not in [`HANDLED_INTERFACES`]. The
+    /// standard `org.freedesktop.DBus` interfaces are left out.
+    fn unhandled_interfaces(&self) -> BTreeSet<String> {
+        self.0
+            .values()
+            .flat_map(|object| object.keys())
+            .filter(|interface| {
+                !HANDLED_INTERFACES.contains(&interface.as_str())
+                    && !interface.starts_with("org.freedesktop.DBus.")
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Find the block referred to by an fstab-style device specification.
+    fn resolve_device(&self, spec: &DeviceSpec) -> Option<Block> {
+        self.get_blocks().find(|block| spec.matches(block))
+    }
+
+    /// Find the block holding the partition table which the given partition belongs to.
+    fn whole_disk_of(&self, block: &Block) -> Option<Block> {
+        self.get_block(&block.partition.as_ref()?.table)
+    }
+
+    /// Every block of the drive, sorted by partition number with the whole disk first, followed
+    /// by the cleartext blocks of any unlocked containers among them.
+    fn blocks_of_drive(&self, drive: &Drive) -> Vec<Block> {
+        let (mut blocks, others): (Vec<_>, Vec<_>) = self
+            .get_blocks()
+            .partition(|block| block.drive == drive.path);
+        blocks.sort_by_key(|block| block.partition.as_ref().map_or(0, |p| p.number));
+
+        let mut index = 0;
+        while index < blocks.len() {
+            let backing = blocks[index].path.clone();
+            blocks.extend(
+                others
+                    .iter()
+                    .filter(|block| block.crypto_backing_device == backing)
+                    .cloned(),
+            );
+            index += 1;
+        }
+        blocks
+    }
+
+    /// The blocks stacked on a block, such as the cleartext block of an unlocked container, or
+    /// the active logical volumes of the LVM volume group which it is a physical volume of,
+    /// along with the blocks stacked on those in turn. Each block comes before the block it is
+    /// stacked on, which is the order to tear them down in.
+    fn stacked_on(&self, block: &Block) -> Vec<Block> {
+        let blocks = self.get_blocks().collect::<Vec<_>>();
+        let mut stacked = Vec::new();
+        self.stack_onto(&blocks, &block.path, &mut stacked);
+        stacked
+    }
+
+    fn stack_onto(&self, blocks: &[Block], path: &str, stacked: &mut Vec<Block>) {
+        let volume_group = self
+            .object(path)
+            .and_then(|object| object.get(PHYSICAL_VOLUME))
+            .and_then(|pv| pv.get(VOLUME_GROUP))
+            .and_then(get_string);
+        let logical_volumes = self
+            .0
+            .values()
+            .filter_map(|object| object.get(LOGICAL_VOLUME))
+            .filter(|lv| {
+                volume_group.is_some() && lv.get(VOLUME_GROUP).and_then(get_string) == volume_group
+            })
+            .filter_map(|lv| lv.get(BLOCK_DEVICE).and_then(get_string))
+            .collect::<Vec<_>>();
+
+        for block in blocks {
+            let is_stacked =
+                block.crypto_backing_device == path || logical_volumes.contains(&block.path);
+            if is_stacked && !stacked.iter().any(|b: &Block| b.path == block.path) {
+                self.stack_onto(blocks, &block.path, stacked);
+                stacked.push(block.clone());
+            }
+        }
+    }
+
+    /// Blocks with a file system on removable drives, sorted by device.
+    fn removable_filesystems(&self) -> Vec<Block> {
+        let removable = self
+            .get_drives()
+            .filter(|drive| drive.removable)
+            .map(|drive| drive.path)
+            .collect::<Vec<_>>();
+        let mut blocks = self
+            .get_blocks()
+            .filter(|block| block.table.is_none() && removable.contains(&block.drive))
+            .filter(|block| self.has_interface(&block.path, filesystem::DEST))
+            .collect::<Vec<_>>();
+        blocks.sort_by(|a, b| a.device.cmp(&b.device));
+        blocks
+    }
+
+    /// Whether the given block paths differ from the paths of the cached blocks.
+    fn block_paths_differ(&self, paths: &[dbus::Path<'static>]) -> bool {
+        let cached = self
+            .0
+            .iter()
+            .filter(|(_, objects)| objects.contains_key(BLOCK))
+            .count();
+        cached != paths.len() || paths.iter().any(|path| !self.has_interface(path, BLOCK))
+    }
+
+    /// The paths of the loop devices which are backed by the given file.
+    fn loops_backed_by(&self, file: &Path) -> Vec<dbus::Path<'static>> {
+        let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        self.0
+            .iter()
+            .filter_map(|(path, objects)| {
+                let backing_file = objects
+                    .get(loop_device::DEST)?
+                    .get(loop_device::BACKING_FILE)?;
+                if Path::new(&get_byte_array(backing_file)?) == file {
+                    Some(path.clone())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Pair each unlocked encrypted block with its cleartext block.
+    fn unlocked_pairs(&self) -> Vec<(Block, Block)> {
+        let blocks = self.get_blocks().collect::<Vec<_>>();
+        blocks
+            .iter()
+            .filter_map(|container| {
+                let cleartext = &container.encrypted.as_ref()?.cleartext_device;
+                blocks
+                    .iter()
+                    .find(|block| block.path == *cleartext)
+                    .map(|cleartext| (container.clone(), cleartext.clone()))
+            })
+            .collect()
+    }
+
+    /// The unlocked encrypted blocks which aren't stacked on another unlocked container, such as
+    /// a LUKS container within another, or on an LVM logical volume within one.
+    fn outermost_unlocked(&self) -> Vec<Block> {
+        let containers = self
+            .unlocked_pairs()
+            .into_iter()
+            .map(|(container, _)| container)
+            .collect::<Vec<_>>();
+        let nested = containers
+            .iter()
+            .flat_map(|container| self.stacked_on(container))
+            .map(|block| block.path)
+            .collect::<Vec<_>>();
+        containers
+            .into_iter()
+            .filter(|container| !nested.contains(&container.path))
+            .collect()
+    }
+
+    /// The resize calls, as `(path, interface, size)`, which resize an unlocked encrypted
+    /// partition to `new_size` along with its contents, in the order they must be made.
+    fn encrypted_resize_plan(
+        &self,
+        container: &Block,
+        new_size: u64,
+    ) -> Result<Vec<(String, &'static str, u64)>> {
+        if container.partition.is_none() {
+            return Err(UdisksError::NotAPartition(container.path.clone()));
+        }
+        let encrypted = container
+            .encrypted
+            .as_ref()
+            .ok_or_else(|| UdisksError::NotEncrypted(container.path.clone()))?;
+        if !encrypted.is_unlocked() {
+            return Err(UdisksError::Locked(container.path.clone()));
+        }
+        let cleartext = &encrypted.cleartext_device;
+        let has_filesystem = self.has_interface(cleartext, filesystem::DEST);
+
+        let mut plan = Vec::new();
+        if new_size > container.size {
+            // Grow from the outside in, with each layer filling the one it's within.
+            plan.push((container.path.clone(), partition::DEST, new_size));
+            plan.push((container.path.clone(), encrypted::DEST, 0));
+            if has_filesystem {
+                plan.push((cleartext.clone(), filesystem::DEST, 0));
+            }
+        } else if new_size < container.size {
+            // Shrink from the inside out, leaving room for the LUKS header. A cleartext size of
+            // zero would have UDisks fill the container instead, so it's refused along with an
+            // unknown header size.
+            if encrypted.metadata_size == 0 {
+                return Err(UdisksError::UnknownMetadataSize(container.path.clone()));
+            }
+            if new_size <= encrypted.metadata_size {
+                return Err(UdisksError::SizeTooSmall(container.path.clone()));
+            }
+            let cleartext_size = new_size - encrypted.metadata_size;
+            if has_filesystem {
+                plan.push((cleartext.clone(), filesystem::DEST, cleartext_size));
+            }
+            plan.push((container.path.clone(), encrypted::DEST, cleartext_size));
+            plan.push((container.path.clone(), partition::DEST, new_size));
+        }
+        Ok(plan)
+    }
+
+    /// The cached objects and interfaces which have a `Size` property that may change on resize.
+    fn sized_interfaces(&self) -> Vec<(dbus::Path<'static>, &'static str)> {
+        let mut sized = Vec::new();
+        for (path, objects) in &self.0 {
+            for &interface in SIZED_INTERFACES {
+                if objects.contains_key(interface) {
+                    sized.push((path.clone(), interface));
+                }
+            }
+        }
+        sized
+    }
+
+    /// The interfaces of the cached object at the given path.
+    fn interfaces_of(&self, path: &str) -> Vec<String> {
+        self.object(path)
+            .map(|object| object.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Replace the cached properties of an interface of the object at the given path.
+    fn set_interface(&mut self, path: &str, interface: String, properties: KeyVariant) {
+        if let Some(interfaces) = self.object_mut(path) {
+            interfaces.insert(interface, properties);
+        }
+    }
+
+    fn set_mount_points(&mut self, path: &str, mount_points: Vec<Vec<u8>>) {
+        let object = self.object_mut(path);
+        if let Some(properties) = object.and_then(|o| o.get_mut(filesystem::DEST)) {
+            properties.insert(
+                filesystem::MOUNT_POINTS.to_owned(),
+                Variant(Box::new(mount_points)),
+            );
+        }
+    }
+
+    fn set_size(&mut self, path: &dbus::Path<'static>, interface: &str, size: u64) {
+        if let Some(properties) = self.0.get_mut(path).and_then(|o| o.get_mut(interface)) {
+            properties.insert(SIZE.to_owned(), Variant(Box::new(size)));
+        }
+    }
+}
+
+/// Configures and builds a [`UDisks2`].
+///
+/// ```no_run
+/// use dbus_udisks2::UDisks2Builder;
+/// use std::time::Duration;
+///
+/// let udisks2 = UDisks2Builder::default()
+///     .timeout(Duration::from_secs(10))
+///     .retries(2)
+///     .build()
+///     .unwrap();
+/// ```
+pub struct UDisks2Builder {
+    conn: Option<blocking::Connection>,
+    timeout: Option<Duration>,
+    retries: u32,
+    update: bool,
+}
+
+impl Default for UDisks2Builder {
+    fn default() -> Self {
+        UDisks2Builder {
+            conn: None,
+            timeout: None,
+            retries: 0,
+            update: true,
+        }
+    }
+}
+
+impl UDisks2Builder {
+    /// The connection to use, instead of connecting to the system bus.
+    pub fn connection(mut self, conn: blocking::Connection) -> Self {
+        self.conn = Some(conn);
+        self
+    }
+
+    /// The timeout of method calls and property reads which don't take one explicitly. Defaults
+    /// to 3 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How many times [`update`][UDisks2::update] retries fetching the managed objects after a
+    /// failure. Defaults to 0.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Whether to [`update`][UDisks2::update] the cache when building. Defaults to `true`; if
+    /// `false`, the cache is empty until the first `update`.
+    pub fn update_on_build(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    pub fn build(self) -> Result<UDisks2, dbus::Error> {
+        let conn = match self.conn {
+            Some(conn) => conn,
+            None => blocking::Connection::new_system()?,
+        };
+
+        let mut udisks2 = UDisks2 {
+            conn,
+            cache: Default::default(),
+            timeout: self.timeout.unwrap_or(DEFAULT_TIMEOUT),
+            retries: self.retries,
+        };
+
+        if self.update {
+            udisks2.update()?;
+        }
+        Ok(udisks2)
     }
 }
 
 pub struct UDisks2 {
     conn: blocking::Connection,
     cache: DiskCache,
+    timeout: Duration,
+    retries: u32,
 }
 
 impl UDisks2 {
+    /// Connect to the system bus and fetch the managed objects, with the default options of
+    /// [`UDisks2Builder`].
     pub fn new() -> Result<Self, dbus::Error> {
-        let mut udisks2 = Self {
-            conn: blocking::Connection::new_system()?,
-            cache: Default::default(),
-        };
+        UDisks2Builder::default().build()
+    }
 
-        udisks2.update()?;
+    /// Like [`new`][Self::new], but a failure of the initial [`update`][Self::update] is logged
+    /// rather than returned, so that a long-running service may start despite a transient error
+    /// on the bus. Only failing to connect is an error.
+    ///
+    /// The cache is empty until an `update` succeeds, which the caller is expected to retry.
+    pub fn new_lenient() -> Result<Self, dbus::Error> {
+        let mut udisks2 = UDisks2Builder::default().update_on_build(false).build()?;
+        if let Err(why) = udisks2.update() {
+            log::warn!("initial update of the UDisks2 objects failed: {}", why);
+        }
         Ok(udisks2)
     }
 
@@ -90,15 +571,128 @@ impl UDisks2 {
         &'a self,
         path: impl Into<dbus::Path<'a>>,
     ) -> blocking::Proxy<&blocking::Connection> {
-        blocking::Proxy::new(DEST, path, Duration::from_millis(3000), &self.conn)
+        self.proxy_with_timeout(path, self.timeout)
+    }
+
+    fn proxy_with_timeout<'a>(
+        &'a self,
+        path: impl Into<dbus::Path<'a>>,
+        timeout: Duration,
+    ) -> blocking::Proxy<'a, &'a blocking::Connection> {
+        blocking::Proxy::new(DEST, path, timeout, &self.conn)
     }
 
     /// Refresh the managed objects fetched from the DBus server.
     pub fn update(&mut self) -> Result<(), dbus::Error> {
-        self.cache.0 = self.proxy(PATH).get_managed_objects()?;
+        let mut attempts = 0;
+        loop {
+            match self.proxy(PATH).get_managed_objects() {
+                Ok(objects) => {
+                    self.cache.0 = objects;
+                    return Ok(());
+                }
+                Err(_) if attempts < self.retries => attempts += 1,
+                Err(why) => return Err(why),
+            }
+        }
+    }
+
+    /// Cheaply check whether block devices were added or removed since the last
+    /// [`update`][Self::update], by comparing the paths returned by the manager's
+    /// `GetBlockDevices` against the cache. A polling loop may use this to skip updates when
+    /// nothing changed.
+    ///
+    /// Only the set of block paths is compared. Changes to the properties of existing blocks,
+    /// such as mount points, labels, or sizes, are not detected, nor are drives without any
+    /// blocks, such as an empty card reader, coming and going.
+    pub fn has_changes(&self) -> Result<bool, dbus::Error> {
+        let options = options_builder().build();
+        let (paths,): (Vec<dbus::Path<'static>>,) = self.proxy(manager::PATH).method_call(
+            manager::DEST,
+            manager::GET_BLOCK_DEVICES,
+            (options,),
+        )?;
+        Ok(self.cache.block_paths_differ(&paths))
+    }
+
+    /// Refresh only the sizes of the cached blocks and partitions, which is much cheaper than a
+    /// full [`update`][Self::update] after resizing. Any other changes, such as added or removed
+    /// devices, still require an `update`.
+    pub fn refresh_sizes(&mut self) -> Result<(), dbus::Error> {
+        let mut sizes = Vec::new();
+        for (path, interface) in self.cache.sized_interfaces() {
+            let size: u64 = self.proxy(path.clone()).get(interface, SIZE)?;
+            sizes.push((path, interface, size));
+        }
+        for (path, interface, size) in sizes {
+            self.cache.set_size(&path, interface, size);
+        }
         Ok(())
     }
 
+    /// Re-read every property of a single cached object, such as a block, which is much cheaper
+    /// than a full [`update`][Self::update]. Objects which aren't cached are ignored.
+    ///
+    /// UDisks only re-reads a block's state, such as `read_only`, when the kernel sends a uevent
+    /// for it. Toggling read-only with `blockdev --setro` sends none, so UDisks, and this, may
+    /// report the old value until the next change event. UDisks has no method for setting a
+    /// block read-only; that must be done through the kernel.
+    pub fn refresh_block(&mut self, path: &str) -> Result<(), dbus::Error> {
+        let mut interfaces = Vec::new();
+        for interface in self.cache.interfaces_of(path) {
+            let properties = self.proxy(path).get_all(&interface)?;
+            interfaces.push((interface, properties));
+        }
+        for (interface, properties) in interfaces {
+            self.cache.set_interface(path, interface, properties);
+        }
+        Ok(())
+    }
+
+    /// Refresh a block in the cache after changing it, and return it. The whole cache is updated
+    /// if the block wasn't cached yet.
+    fn refreshed_block(&mut self, path: &str) -> Result<Block> {
+        if self.cache.interfaces_of(path).is_empty() {
+            self.update()?;
+        } else {
+            self.refresh_block(path)?;
+        }
+        self.get_block(path)
+            .ok_or_else(|| UdisksError::NoSuchBlock(path.to_owned()))
+    }
+
+    /// Read a block afresh from UDisks after changing it, and return it, leaving the cache as it
+    /// was.
+    fn read_block(&self, path: &str) -> Result<Block> {
+        let objects = self.proxy(PATH).get_managed_objects()?;
+        DiskCache(objects)
+            .get_block(path)
+            .ok_or_else(|| UdisksError::NoSuchBlock(path.to_owned()))
+    }
+
+    /// Read a drive afresh from UDisks, without a full [`update`][Self::update], such as for a
+    /// detail view refreshed on a timer. Only its `org.freedesktop.UDisks2.Drive` interface is
+    /// read, and the cache is left as it was.
+    ///
+    /// Returns [`UdisksError::NoSuchDrive`] if the drive no longer exists.
+    pub fn refresh_drive(&self, drive: &Drive) -> Result<Drive> {
+        let properties = match self.proxy(drive).get_all(drive::DEST) {
+            Ok(properties) => properties,
+            // GDBus reports a removed object as an unknown method, and a removed interface as
+            // an invalid argument.
+            Err(ref why)
+                if manager::is_missing_property(why) || manager::is_missing_method(why) =>
+            {
+                return Err(UdisksError::NoSuchDrive(drive.path.clone()))
+            }
+            Err(why) => return Err(why.into()),
+        };
+        let mut objects = DbusObjects::new();
+        objects.insert(drive::DEST.to_owned(), properties);
+        Drive::from_dbus(&drive.path, &objects)
+            .ok_or_else(|| UdisksError::NoSuchDrive(drive.path.clone()))
+    }
+
     /// Find the drive that corresponds to the given dbus object path.
     pub fn get_drive(&self, path: &str) -> Option<Drive> {
         self.cache.get_drive(path)
@@ -109,28 +703,212 @@ impl UDisks2 {
         self.cache.get_drives()
     }
 
+    /// Other drives which are the same physical device as the given drive, reached through
+    /// another path, such as with multipath. These share its `sibling_id`. Returns nothing if
+    /// the drive has no `sibling_id`.
+    pub fn sibling_drives(&self, drive: &Drive) -> Vec<Drive> {
+        self.cache.sibling_drives(drive)
+    }
+
+    /// Drives with removable media but no media present, such as an empty card reader slot or
+    /// optical drive. These have no blocks, so they are absent from [`Disks`].
+    pub fn get_empty_drives<'a>(&'a self) -> impl Iterator<Item = Drive> + 'a {
+        self.cache
+            .get_drives()
+            .filter(|drive| drive.media_removable && !drive.media_available)
+    }
+
+    /// Blocks with a mountable file system on removable drives, sorted by device. This is the
+    /// set of removable media which a file manager would list.
+    ///
+    /// Both partitions and whole-disk blocks are included, as removable media such as USB
+    /// sticks are often formatted without a partition table (a "superfloppy"), with the file
+    /// system directly on the whole disk. Blocks holding a partition table are excluded.
+    pub fn removable_filesystems(&self) -> Vec<Block> {
+        self.cache.removable_filesystems()
+    }
+
+    /// The bcache devices in the cache. These require the `bcache` module of UDisks, which may
+    /// be loaded with [`enable_modules`][Self::enable_modules] followed by an
+    /// [`update`][Self::update].
+    pub fn get_bcache_devices<'a>(&'a self) -> impl Iterator<Item = Bcache> + 'a {
+        self.cache.get_bcache_devices()
+    }
+
+    /// The zram devices in the cache. These require the `zram` module of UDisks, which may be
+    /// loaded with [`enable_modules`][Self::enable_modules] followed by an
+    /// [`update`][Self::update].
+    pub fn get_zram_devices<'a>(&'a self) -> impl Iterator<Item = Zram> + 'a {
+        self.cache.get_zram_devices()
+    }
+
+    /// An iterator of the running jobs in the cache.
+    pub fn get_jobs<'a>(&'a self) -> impl Iterator<Item = Job> + 'a {
+        self.cache.get_jobs()
+    }
+
+    /// The running jobs in the cache which affect the object at the given dbus object path,
+    /// such as a block being formatted.
+    pub fn jobs_of(&self, path: &str) -> Vec<Job> {
+        self.cache.jobs_of(path)
+    }
+
     /// Find the block that corresponds to the given dbus object path.
     pub fn get_block(&self, path: &str) -> Option<Block> {
         self.cache.get_block(path)
     }
 
+    /// Find the block with the given device node, such as `/dev/sda1`. The preferred device
+    /// node and symlinks of blocks are matched too, such as `/dev/mapper/cryptroot` or
+    /// `/dev/disk/by-uuid/...`.
+    pub fn get_block_by_device(&self, device: &Path) -> Option<Block> {
+        self.cache.get_block_by_device(device)
+    }
+
+    /// Find the block referred to by a device specification, as found in `/etc/fstab`, such as
+    /// `UUID=...` or `/dev/sda1`. See [`DeviceSpec::parse`] for the supported forms.
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use dbus_udisks2::{DeviceSpec, UDisks2};
+    ///
+    /// let udisks = UDisks2::new()?;
+    /// let home = udisks.resolve_device(&DeviceSpec::parse("LABEL=home"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_device(&self, spec: &DeviceSpec) -> Option<Block> {
+        self.cache.resolve_device(spec)
+    }
+
+    /// Find the whole-disk block, which holds the partition table, that the given partition
+    /// belongs to. Returns `None` if the block isn't a partition.
+    pub fn whole_disk_of(&self, block: &Block) -> Option<Block> {
+        self.cache.whole_disk_of(block)
+    }
+
+    /// Every block belonging to the drive: the whole disk, its partitions sorted by number, and
+    /// then the cleartext blocks of any unlocked containers among them. This is the raw grouping
+    /// from the cache, before [`Disks`] splits it into parents and partitions.
+    ///
+    /// Blocks are matched by their `drive`, except for cleartext blocks, which UDisks does not
+    /// associate with a drive, and so are matched by their `crypto_backing_device`.
+    pub fn blocks_of_drive(&self, drive: &Drive) -> Vec<Block> {
+        self.cache.blocks_of_drive(drive)
+    }
+
     /// An iterator of `Block` objects fetched from the inner cached managed objects.
     pub fn get_blocks<'a>(&'a self) -> impl Iterator<Item = Block> + 'a {
         self.cache.get_blocks()
     }
 
+    /// The names of interfaces found on the cached objects which this crate doesn't parse, such
+    /// as `org.freedesktop.UDisks2.MDRaid`, or those of modules added by newer versions of
+    /// UDisks. Their properties are still available through [`raw_objects`][Self::raw_objects].
+    ///
+    /// The standard `org.freedesktop.DBus` interfaces are left out. Properties which are not
+    /// parsed on handled interfaces are not covered, and are instead logged at the debug level
+    /// while parsing.
+    pub fn unhandled_interfaces(&self) -> BTreeSet<String> {
+        self.cache.unhandled_interfaces()
+    }
+
+    /// Every block which isn't a component of another device, such as a RAID member or an
+    /// unlocked encrypted container. See [`Block::is_component`] for the criteria.
+    pub fn get_mountable_blocks<'a>(&'a self) -> impl Iterator<Item = Block> + 'a {
+        self.get_blocks().filter(|block| !block.is_component())
+    }
+
+    /// Find the block which is mounted at the given path, such as `/` for the root file system.
+    ///
+    /// The path is canonicalized first if possible, so that relative paths and paths through
+    /// symlinks are resolved. It must be a mount point itself, rather than a path within one.
+    ///
+    /// If no mount point matches exactly, the mount points are canonicalized too, to find file
+    /// systems mounted at paths which have since become symlinks. This touches the file system
+    /// for every mount point, which may block on unresponsive network mounts.
+    pub fn block_for_mount(&self, path: &Path) -> Option<Block> {
+        self.cache.block_for_mount(path)
+    }
+
+    /// Guess whether mounting the block will require authentication, following the default
+    /// polkit policy of UDisks: devices considered to be system devices, which includes any block
+    /// of a non-removable drive, require administrator authentication, while others may be
+    /// mounted by users of an active local session without it.
+    ///
+    /// This is a heuristic. Local polkit rules, inactive or remote sessions, and devices attached
+    /// to other seats may change the outcome, which is only known for certain by attempting the
+    /// mount.
+    pub fn mount_requires_auth(&self, block: &Block) -> bool {
+        block.hint_system
+            || matches!(self.cache.get_drive(&block.drive), Some(drive) if !drive.removable)
+    }
+
+    /// Every unlocked encrypted block, paired with the cleartext block it is mapped to, as
+    /// `(container, cleartext)`. Locked containers are skipped.
+    pub fn unlocked_pairs(&self) -> Vec<(Block, Block)> {
+        self.cache.unlocked_pairs()
+    }
+
+    /// Read a property of a UDisks object directly over D-Bus, for properties which this crate
+    /// does not model. The cache is neither used nor updated.
+    ///
+    /// `V` must match the D-Bus signature of the property, such as `u64` for `t`, or
+    /// `dbus::Path<'static>` for `o`, or the call fails.
+    pub fn get_property<V: for<'z> dbus::arg::Get<'z> + 'static>(
+        &self,
+        path: &str,
+        interface: &str,
+        name: &str,
+    ) -> Result<V, dbus::Error> {
+        self.proxy(path).get(interface, name)
+    }
+
+    /// Write a property of a UDisks object directly over D-Bus, for properties which this crate
+    /// does not model. Call [`update`][Self::update] afterwards to see the change in the cache.
+    ///
+    /// `V` must match the D-Bus signature of the property, or the call fails.
+    pub fn set_property<V: dbus::arg::Arg + dbus::arg::Append>(
+        &self,
+        path: &str,
+        interface: &str,
+        name: &str,
+        value: V,
+    ) -> Result<(), dbus::Error> {
+        self.proxy(path).set(interface, name, value)
+    }
+
+    /// The cached managed objects, as returned by UDisks, keyed by their object paths. Each
+    /// object maps its interfaces to their properties.
+    ///
+    /// This is intended for debugging, and for reaching interfaces and properties which this
+    /// crate does not parse. Prefer the parsed getters, such as [`get_blocks`][Self::get_blocks].
+    pub fn raw_objects(&self) -> &HashMap<dbus::Path<'static>, DbusObjects> {
+        &self.cache.0
+    }
+
+    /// Parse all drives and blocks from the cache at once.
+    ///
+    /// Both are parsed from the same cached state. As the cache is only ever replaced through
+    /// `&mut self`, such as by [`update`][Self::update], no update can be applied in between, so
+    /// the blocks always refer to the drives of the same snapshot.
+    pub fn snapshot(&self) -> (Vec<Drive>, Vec<Block>) {
+        (
+            self.cache.get_drives().collect(),
+            self.cache.get_blocks().collect(),
+        )
+    }
+
     /// Update the S.M.A.R.T. attributes of a drive. You may pass either a `&`[`Drive`] or `&str`
     /// which is a path to a drive, starting with `/org/freedesktop/UDisks2/drives/`.
     pub fn smart_update<'a>(
         &'a self,
         drive: impl Into<dbus::Path<'a>>,
-        allow_wakeup: bool,
+        wakeup: Wakeup,
     ) -> Result<(), dbus::Error> {
         let proxy = self.proxy(drive);
         let mut options = KeyVariant::<&str>::new();
-        if !allow_wakeup {
-            options.insert(NO_WAKEUP, Variant(Box::new(true)));
-        }
+        wakeup.apply(&mut options);
         proxy.method_call(smart::DEST, smart::UPDATE, (options,))
     }
 
@@ -139,7 +917,7 @@ impl UDisks2 {
     pub fn smart_attributes<'a>(
         &'a self,
         drive: impl Into<dbus::Path<'a>>,
-        allow_wakeup: bool,
+        wakeup: Wakeup,
     ) -> Result<SmartValue, dbus::Error> {
         let proxy = self.proxy(drive);
         if !proxy.get::<bool>(smart::DEST, smart::SUPPORTED)? {
@@ -153,9 +931,7 @@ impl UDisks2 {
             return Ok(SmartValue::NotUpdated);
         }
         let mut options = KeyVariant::<&str>::new();
-        if !allow_wakeup {
-            options.insert(NO_WAKEUP, Variant(Box::new(true)));
-        }
+        wakeup.apply(&mut options);
         let (attrs,): (Vec<RawSmartAttribute>,) =
             proxy.method_call(smart::DEST, smart::GET_ATTRS, (options,))?;
         Ok(SmartValue::Enabled(SmartData {
@@ -170,7 +946,721 @@ impl UDisks2 {
                 .get::<String>(smart::DEST, smart::STATUS)?
                 .parse()
                 .unwrap_or(SmartStatus::Unknown),
+            selftest_percent_remaining: Some(proxy.get(smart::DEST, smart::PERCENT_REMAINING)?)
+                .filter(|percent| *percent >= 0),
             attributes: attrs.into_iter().map(Into::into).collect(),
         }))
     }
+
+    /// Mount the file system of a block, returning the path it was mounted at along with the
+    /// block. You may pass either a `&`[`Block`] or `&str` which is a path to a block, starting
+    /// with `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// Only the `MountPoints` of the block are read back and patched into the cache, which is
+    /// much cheaper than an [`update`][Self::update], and leaves other cached objects as they
+    /// were. The returned block reflects the new mount point.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn mount<'a>(
+        &mut self,
+        block: impl Into<dbus::Path<'a>>,
+        options: &MountOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<(PathBuf, Block)> {
+        let block = block.into();
+        let proxy = self.proxy_with_timeout(block.clone(), timeout);
+        let (mount_path,): (String,) = proxy.method_call(
+            filesystem::DEST,
+            filesystem::MOUNT,
+            (options.build(interactive.into()),),
+        )?;
+        let mount_points = proxy.get(filesystem::DEST, filesystem::MOUNT_POINTS)?;
+        self.cache.set_mount_points(&block, mount_points);
+        let block = self
+            .get_block(&block)
+            .ok_or_else(|| UdisksError::NoSuchBlock(block.to_string()))?;
+        Ok((PathBuf::from(mount_path), block))
+    }
+
+    /// Unmount the file system of a block. You may pass either a `&`[`Block`] or `&str` which is
+    /// a path to a block, starting with `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    ///
+    /// UDisks replies once the file system is unmounted, after flushing its data, which may take
+    /// long for slow devices. See [`unmount_no_wait`][Self::unmount_no_wait] to return
+    /// immediately.
+    pub fn unmount<'a>(
+        &'a self,
+        block: impl Into<dbus::Path<'a>>,
+        options: &UnmountOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let proxy = self.proxy_with_timeout(block, timeout);
+        proxy
+            .method_call(
+                filesystem::DEST,
+                filesystem::UNMOUNT,
+                (options.build(interactive.into()),),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Start unmounting the file system of a block, without waiting for UDisks to finish. You
+    /// may pass either a `&`[`Block`] or `&str` which is a path to a block, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// UDisks has no `no_block` option for unmounting, and doesn't return a job, so the call is
+    /// instead sent without asking for a reply. Only failing to send it is an error: whether the
+    /// unmount succeeded, or failed because the file system is busy, is never reported. While it
+    /// runs, UDisks shows the unmount as a job with the operation `"filesystem-unmount"`, found
+    /// with [`jobs_of`][Self::jobs_of] after an [`update`][Self::update]. Once the block's
+    /// `mount_points` are empty, it has finished.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn unmount_no_wait<'a>(
+        &'a self,
+        block: impl Into<dbus::Path<'a>>,
+        options: &UnmountOptions,
+        interactive: impl Into<Interactivity>,
+    ) -> Result<()> {
+        let mut message = Message::method_call(
+            &DEST.into(),
+            &block.into(),
+            &filesystem::DEST.into(),
+            &filesystem::UNMOUNT.into(),
+        )
+        .append1(options.build(interactive.into()));
+        message.set_no_reply(true);
+        self.conn
+            .send(message)
+            .map_err(|()| dbus::Error::new_failed("failed to send Unmount"))?;
+        // Messages are only queued by `send`, and would otherwise wait for the next call.
+        self.conn.channel().flush();
+        Ok(())
+    }
+
+    /// Format a block with a new file system of the type `fstype`, such as `"ext4"`, or
+    /// `"empty"` to only wipe existing signatures. You may pass either a `&`[`Block`] or `&str`
+    /// which is a path to a block, starting with `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// The block is read back from UDisks and returned, so that its `id_type`, `id_label` and
+    /// `id_uuid` describe the new file system. With [`no_block`][FormatOptions::no_block], UDisks
+    /// may not have probed the new file system by then. As with [`unmount`][Self::unmount], the
+    /// cache is left as it was; call [`update`][Self::update] to refresh it.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn format<'a>(
+        &self,
+        block: impl Into<dbus::Path<'a>>,
+        fstype: &str,
+        options: &FormatOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<Block> {
+        let block = block.into();
+        self.proxy_with_timeout(block.clone(), timeout)
+            .method_call::<(), _, _, _>(
+                format::DEST,
+                format::FORMAT,
+                (fstype, options.build(interactive.into())),
+            )?;
+        self.read_block(&block)
+    }
+
+    /// Create a partition in the partition table of a block, returning the new partition. You
+    /// may pass either a `&`[`Block`] or `&str` which is a path to a block, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// With [`aligned`][PartitionOptions::aligned], the offset and size are first aligned to
+    /// [`DEFAULT_ALIGNMENT`][partition::DEFAULT_ALIGNMENT], as described by
+    /// [`align_region`][partition::align_region]. If no aligned space remains,
+    /// [`UdisksError::RegionTooSmall`] is returned, rather than passing a size of zero, which
+    /// UDisks takes to mean as large as possible. A `size` of zero is passed on as is, to fill
+    /// the free space from the aligned offset.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn create_partition<'a>(
+        &mut self,
+        table: impl Into<dbus::Path<'a>>,
+        offset: u64,
+        size: u64,
+        options: &PartitionOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<Block> {
+        let table = table.into();
+        let (offset, size) = options
+            .region(offset, size)
+            .ok_or_else(|| UdisksError::RegionTooSmall(table.to_string()))?;
+        let (partition,): (dbus::Path<'static>,) =
+            self.proxy_with_timeout(table, timeout).method_call(
+                partition::TABLE_DEST,
+                partition::CREATE_PARTITION,
+                (
+                    offset,
+                    size,
+                    options.type_.as_str(),
+                    options.name.as_str(),
+                    options.build(interactive.into()),
+                ),
+            )?;
+        self.refreshed_block(&partition)
+    }
+
+    /// Format the file system of a partition in place, keeping its entry in the partition table.
+    /// The partition type is updated to match the new file system.
+    ///
+    /// Unlike [`format`][Self::format], this refuses blocks which aren't partitions, or which
+    /// contain a partition table, so it can never wipe a whole disk. Extended partitions are
+    /// refused too, with [`UdisksError::ExtendedPartition`], as formatting one would wipe the
+    /// logical partitions within it. As with `format`, the partition is read back and returned.
+    pub fn reformat_partition(
+        &self,
+        partition: &Block,
+        fstype: &str,
+        options: &FormatOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<Block> {
+        if partition.partition.is_none() || partition.table.is_some() {
+            return Err(UdisksError::NotAPartition(partition.path.clone()));
+        }
+        if matches!(partition.partition, Some(ref p) if p.is_container) {
+            return Err(UdisksError::ExtendedPartition(partition.path.clone()));
+        }
+        let proxy = self.proxy_with_timeout(partition, timeout);
+        let mut options = options.build(interactive.into());
+        options.insert(format::UPDATE_PARTITION_TYPE, Variant(Box::new(true)));
+        proxy.method_call::<(), _, _, _>(format::DEST, format::FORMAT, (fstype, options))?;
+        self.read_block(&partition.path)
+    }
+
+    /// Set whether a loop device is detached automatically once it is no longer in use. You may
+    /// pass either a `&`[`Block`] or `&str` which is a path to a loop device, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn loop_set_autoclear<'a>(
+        &'a self,
+        block: impl Into<dbus::Path<'a>>,
+        value: bool,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let block = block.into();
+        if !self.cache.has_interface(&block, loop_device::DEST) {
+            return Err(UdisksError::NotALoopDevice(block.to_string()));
+        }
+        let proxy = self.proxy_with_timeout(block, timeout);
+        let options = options_builder().interactive(interactive).build();
+        proxy
+            .method_call(
+                loop_device::DEST,
+                loop_device::SET_AUTOCLEAR,
+                (value, options),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Lock an unlocked encrypted block, removing its cleartext block. You may pass either a
+    /// `&`[`Block`] or `&str` which is a path to a block, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`. The cleartext block must not be in use.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn lock<'a>(
+        &'a self,
+        block: impl Into<dbus::Path<'a>>,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let proxy = self.proxy_with_timeout(block, timeout);
+        let options = options_builder().interactive(interactive).build();
+        proxy
+            .method_call(encrypted::DEST, encrypted::LOCK, (options,))
+            .map_err(Into::into)
+    }
+
+    /// Resize the LUKS container of an unlocked encrypted block, such that its cleartext block
+    /// is `size` bytes, or fills the block if `size` is `0`. The block itself must already be
+    /// large enough; see [`resize_encrypted_stack`][Self::resize_encrypted_stack] for resizing
+    /// a partition along with its contents.
+    ///
+    /// LUKS2 containers may require a `passphrase` to unlock a keyslot for resizing. It is only
+    /// passed on to UDisks, and never logged.
+    ///
+    /// Returns [`UdisksError::NotEncrypted`] if the block is not encrypted.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn resize_encrypted(
+        &self,
+        block: &Block,
+        size: u64,
+        passphrase: Option<&str>,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        if block.encrypted.is_none() {
+            return Err(UdisksError::NotEncrypted(block.path.clone()));
+        }
+        let mut options = options_builder().interactive(interactive);
+        if let Some(passphrase) = passphrase {
+            options = options.insert(encrypted::PASSPHRASE, passphrase.to_owned());
+        }
+        self.proxy_with_timeout(block, timeout)
+            .method_call(encrypted::DEST, RESIZE, (size, options.build()))
+            .map_err(Into::into)
+    }
+
+    /// Resize an encrypted partition along with the LUKS container and file system within it, in
+    /// the order which keeps the data intact: when growing, the partition, then the container,
+    /// then the file system, each filling the space of the one it's within; when shrinking, the
+    /// file system and container down to `new_size` less the LUKS header, then the partition.
+    ///
+    /// The container must be unlocked, so that its cleartext block can be resized, and a file
+    /// system on it must support resizing in its current state, which for some types means
+    /// unmounted. A cleartext block without a file system, such as an LVM physical volume, is
+    /// left for the caller. LUKS2 containers may require a passphrase to resize, which this does
+    /// not provide; in that case, resize the container with
+    /// [`resize_encrypted`][Self::resize_encrypted].
+    ///
+    /// Returns [`UdisksError::NotAPartition`], [`UdisksError::NotEncrypted`] or
+    /// [`UdisksError::Locked`] before making any changes if `container` is not suitable. When
+    /// shrinking, [`UdisksError::UnknownMetadataSize`] is returned if UDisks doesn't report the
+    /// size of the LUKS header, and [`UdisksError::SizeTooSmall`] if `new_size` leaves no room
+    /// after it. A
+    /// failure part way through leaves the layers at different sizes, but consistent, so the
+    /// operation may be retried. The cached sizes are not updated; call
+    /// [`refresh_sizes`][Self::refresh_sizes] afterwards.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn resize_encrypted_stack(
+        &self,
+        container: &Block,
+        new_size: u64,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let interactive = interactive.into();
+        for (path, interface, size) in self.cache.encrypted_resize_plan(container, new_size)? {
+            let options = options_builder().interactive(interactive).build();
+            self.proxy_with_timeout(path, timeout)
+                .method_call::<(), _, _, _>(interface, RESIZE, (size, options))?;
+        }
+        Ok(())
+    }
+
+    /// Lock every unlocked encrypted block, first tearing down the blocks stacked on it, from the
+    /// top: file systems are unmounted, nested containers are locked, and LVM logical volumes
+    /// are deactivated, so that the cleartext block is no longer in use.
+    ///
+    /// Failures don't stop the remaining blocks from being locked. The result of each is returned
+    /// alongside the encrypted block. Containers nested within another are locked along with it,
+    /// and aren't listed separately.
+    pub fn lock_all(
+        &self,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Vec<(Block, Result<()>)> {
+        let interactive = interactive.into();
+        self.cache
+            .outermost_unlocked()
+            .into_iter()
+            .map(|container| {
+                let result = self
+                    .cache
+                    .stacked_on(&container)
+                    .iter()
+                    .chain(std::iter::once(&container))
+                    .try_for_each(|block| self.tear_down_block(block, interactive, timeout));
+                (container, result)
+            })
+            .collect()
+    }
+
+    /// Unmount the file system of a block, lock it if it's an unlocked container, and deactivate
+    /// it if it's a logical volume, so that the block it's stacked on is no longer in use.
+    fn tear_down_block(
+        &self,
+        block: &Block,
+        interactive: Interactivity,
+        timeout: Duration,
+    ) -> Result<()> {
+        if !block.mount_points.is_empty() {
+            let options = UnmountOptions::default();
+            self.unmount(block, &options, interactive, timeout)?;
+        }
+        if matches!(block.encrypted, Some(ref e) if e.is_unlocked()) {
+            self.lock(block, interactive, timeout)?;
+        }
+        if let Some(ref lv) = block.logical_volume {
+            let options = options_builder().interactive(interactive).build();
+            self.proxy_with_timeout(lv.path.as_str(), timeout)
+                .method_call::<(), _, _, _>(LOGICAL_VOLUME, DEACTIVATE, (options,))?;
+        }
+        Ok(())
+    }
+
+    /// Eject the media of a drive, such as opening the tray of an optical drive. Any file
+    /// systems on it must be unmounted first. UDisks offers no options for ejecting, beyond
+    /// whether the user may be prompted for authentication.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn eject<'a>(
+        &'a self,
+        drive: &'a Drive,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        if !drive.ejectable {
+            return Err(UdisksError::NotEjectable(drive.path.clone()));
+        }
+        let proxy = self.proxy_with_timeout(drive, timeout);
+        let options = options_builder().interactive(interactive).build();
+        proxy
+            .method_call(drive::DEST, drive::EJECT, (options,))
+            .map_err(Into::into)
+    }
+
+    /// The encryption type used by UDisks when formatting with encryption, such as `"luks1"` or
+    /// `"luks2"`. Daemons older than UDisks 2.8, which lack this setting, always use `"luks1"`.
+    pub fn default_encryption_type(&self) -> Result<String> {
+        let result = self
+            .proxy(manager::PATH)
+            .get::<String>(manager::DEST, manager::DEFAULT_ENCRYPTION_TYPE);
+        match result {
+            Err(ref why) if manager::is_missing_property(why) => {
+                Ok(manager::LEGACY_ENCRYPTION_TYPE.to_owned())
+            }
+            result => result.map_err(Into::into),
+        }
+    }
+
+    /// The operations UDisks supports for each file system type it knows of, keyed by type, such
+    /// as `"ext4"`, to gray out unsupported actions such as shrinking XFS.
+    ///
+    /// The types are read from the manager's `SupportedFilesystems`, then each is queried with
+    /// `CanFormat`, `CanResize`, `CanCheck`, and `CanRepair`. Daemons older than UDisks 2.7,
+    /// which lack these methods, return an empty map.
+    pub fn filesystem_capabilities(&self) -> Result<HashMap<String, FsCapabilities>, dbus::Error> {
+        match self.query_filesystem_capabilities() {
+            Err(ref why)
+                if manager::is_missing_property(why) || manager::is_missing_method(why) =>
+            {
+                Ok(HashMap::new())
+            }
+            result => result,
+        }
+    }
+
+    fn query_filesystem_capabilities(
+        &self,
+    ) -> Result<HashMap<String, FsCapabilities>, dbus::Error> {
+        let proxy = self.proxy(manager::PATH);
+        let types: Vec<String> = proxy.get(manager::DEST, manager::SUPPORTED_FILESYSTEMS)?;
+        let mut capabilities = HashMap::new();
+        for fstype in types {
+            // Each method replies with a single struct: `(bs)`, or `(bts)` for `CanResize`.
+            let ((create, _),): ((bool, String),) =
+                proxy.method_call(manager::DEST, manager::CAN_FORMAT, (&fstype,))?;
+            let ((resize, mode, _),): ((bool, u64, String),) =
+                proxy.method_call(manager::DEST, manager::CAN_RESIZE, (&fstype,))?;
+            let ((check, _),): ((bool, String),) =
+                proxy.method_call(manager::DEST, manager::CAN_CHECK, (&fstype,))?;
+            let ((repair, _),): ((bool, String),) =
+                proxy.method_call(manager::DEST, manager::CAN_REPAIR, (&fstype,))?;
+            let resize = if resize { Some(mode) } else { None };
+            let value = FsCapabilities::new(create, check, repair, resize);
+            capabilities.insert(fstype, value);
+        }
+        Ok(capabilities)
+    }
+
+    /// Format a block with an encrypted container, unlocked with `passphrase`, holding a new
+    /// file system of the type `fstype`. The encryption type is given by the
+    /// [`encrypt_type`][FormatOptions::encrypt_type] option, or the system default. You may pass
+    /// either a `&`[`Block`] or `&str` which is a path to a block, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// The container is left unlocked, and the path of its cleartext block is returned.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn format_encrypted<'a>(
+        &'a self,
+        block: impl Into<dbus::Path<'a>>,
+        fstype: &str,
+        passphrase: &str,
+        options: &FormatOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<dbus::Path<'static>> {
+        let proxy = self.proxy_with_timeout(block, timeout);
+        let mut options = options.build(interactive.into());
+        options.insert(
+            format::ENCRYPT_PASSPHRASE,
+            Variant(Box::new(passphrase.to_owned())),
+        );
+        proxy.method_call::<(), _, _, _>(format::DEST, format::FORMAT, (fstype, options))?;
+        proxy
+            .get(encrypted::DEST, encrypted::CLEARTEXT_DEVICE)
+            .map_err(Into::into)
+    }
+
+    /// Get the current power mode of a drive, without waking it up. You may pass either a
+    /// `&`[`Drive`] or `&str` which is a path to a drive, starting with
+    /// `/org/freedesktop/UDisks2/drives/`.
+    pub fn power_state<'a>(&'a self, drive: impl Into<dbus::Path<'a>>) -> Result<PowerState> {
+        let options = options_builder().build();
+        let (state,): (u8,) =
+            self.proxy(drive)
+                .method_call(power::DEST, power::PM_GET_STATE, (options,))?;
+        Ok(PowerState::from(state))
+    }
+
+    /// Load all of the optional UDisks modules, such as `lvm2`, `bcache`, and `zram`, which
+    /// provide additional interfaces. Modules which are not installed are skipped. Follow with an
+    /// [`update`][Self::update] to fetch the new interfaces.
+    pub fn enable_modules(&self) -> Result<()> {
+        self.proxy(manager::PATH)
+            .method_call(manager::DEST, manager::ENABLE_MODULES, (true,))
+            .map_err(Into::into)
+    }
+
+    /// Ask polkit whether this process may perform the action without authenticating, such as
+    /// when running as root or when a local polkit rule allows it. A UI may use this to disable
+    /// actions which the user can't perform, rather than attempting them and failing with
+    /// `NotAuthorized`.
+    ///
+    /// This doesn't prompt for authentication, so `false` is returned both when authentication
+    /// would be required and when the action is denied outright.
+    pub fn check_authorization(&self, action: UdisksAction) -> Result<bool, dbus::Error> {
+        let proxy = blocking::Proxy::new(polkit::BUS_NAME, polkit::PATH, self.timeout, &self.conn);
+        let details: HashMap<&str, &str> = HashMap::new();
+        let ((authorized, _challenge, _details),): ((bool, bool, HashMap<String, String>),) = proxy
+            .method_call(
+                polkit::DEST,
+                polkit::CHECK_AUTHORIZATION,
+                (polkit::subject(), action.action_id(), details, 0u32, ""),
+            )?;
+        Ok(authorized)
+    }
+
+    /// Cancel a running job. You may pass either a `&`[`Job`] or `&str` which is a path to a
+    /// job, starting with `/org/freedesktop/UDisks2/jobs/`. Jobs which are not `cancelable`
+    /// return an error from UDisks.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn cancel_job<'a>(
+        &'a self,
+        job: impl Into<dbus::Path<'a>>,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let options = options_builder().interactive(interactive).build();
+        self.proxy_with_timeout(job, timeout)
+            .method_call(job::DEST, job::CANCEL, (options,))
+            .map_err(Into::into)
+    }
+
+    /// Delete every loop device which is backed by the given file, returning how many were
+    /// deleted. Loop devices are matched from the cache, so it should be up to date.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub fn loop_delete_by_backing_file(
+        &self,
+        file: &Path,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<usize> {
+        let interactive = interactive.into();
+        let loops = self.cache.loops_backed_by(file);
+        for path in &loops {
+            let options = options_builder().interactive(interactive).build();
+            self.proxy_with_timeout(path.clone(), timeout)
+                .method_call::<(), _, _, _>(loop_device::DEST, loop_device::DELETE, (options,))?;
+        }
+        Ok(loops.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINER: &str = "/org/freedesktop/UDisks2/block_devices/sda1";
+    const GIB: u64 = 1 << 30;
+
+    fn container(metadata_size: u64) -> Block {
+        Block {
+            path: CONTAINER.into(),
+            size: 4 * GIB,
+            partition: Some(Partition::default()),
+            encrypted: Some(Encrypted {
+                metadata_size,
+                cleartext_device: "/org/freedesktop/UDisks2/block_devices/dm_2d0".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn object_path(path: &str) -> Variant<Box<dyn dbus::arg::RefArg>> {
+        Variant(Box::new(dbus::Path::from(path.to_owned())))
+    }
+
+    fn block(crypto_backing_device: &str) -> KeyVariant {
+        let mut block = KeyVariant::new();
+        block.insert(
+            "CryptoBackingDevice".into(),
+            object_path(crypto_backing_device),
+        );
+        block.insert("Drive".into(), object_path("/"));
+        block
+    }
+
+    /// A cache of objects, each given as its path and interfaces.
+    fn cache(objects: Vec<(&str, Vec<(&str, KeyVariant)>)>) -> DiskCache {
+        let mut cache = DiskCache::default();
+        for (path, interfaces) in objects {
+            let interfaces = interfaces
+                .into_iter()
+                .map(|(interface, properties)| (interface.to_owned(), properties))
+                .collect();
+            cache
+                .0
+                .insert(dbus::Path::from(path.to_owned()), interfaces);
+        }
+        cache
+    }
+
+    #[test]
+    fn finds_stacked_blocks() {
+        // An LVM volume group on an unlocked container, with a logical volume in it.
+        const CLEARTEXT: &str = "/org/freedesktop/UDisks2/block_devices/dm_2d0";
+        const LV_BLOCK: &str = "/org/freedesktop/UDisks2/block_devices/dm_2d1";
+        const VG: &str = "/org/freedesktop/UDisks2/lvm/vg0";
+        const LV: &str = "/org/freedesktop/UDisks2/lvm/vg0/data";
+
+        let mut pv = KeyVariant::new();
+        pv.insert(VOLUME_GROUP.into(), object_path(VG));
+        let mut lv = KeyVariant::new();
+        lv.insert(VOLUME_GROUP.into(), object_path(VG));
+        lv.insert(BLOCK_DEVICE.into(), object_path(LV_BLOCK));
+        let mut lv_link = KeyVariant::new();
+        lv_link.insert("LogicalVolume".into(), object_path(LV));
+
+        let cache = cache(vec![
+            (CONTAINER, vec![(BLOCK, block("/"))]),
+            (
+                CLEARTEXT,
+                vec![(BLOCK, block(CONTAINER)), (PHYSICAL_VOLUME, pv)],
+            ),
+            (LV, vec![(LOGICAL_VOLUME, lv)]),
+            (
+                LV_BLOCK,
+                vec![
+                    (BLOCK, block("/")),
+                    ("org.freedesktop.UDisks2.Block.LVM2", lv_link),
+                ],
+            ),
+        ]);
+
+        let container = cache.get_block(CONTAINER).unwrap();
+        let stacked = cache
+            .stacked_on(&container)
+            .into_iter()
+            .map(|block| block.path)
+            .collect::<Vec<_>>();
+        assert_eq!(stacked, vec![LV_BLOCK, CLEARTEXT]);
+        assert!(cache
+            .stacked_on(&cache.get_block(LV_BLOCK).unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn finds_outermost_unlocked() {
+        // A LUKS container within another, both unlocked.
+        const INNER: &str = "/org/freedesktop/UDisks2/block_devices/dm_2d0";
+        const CLEARTEXT: &str = "/org/freedesktop/UDisks2/block_devices/dm_2d1";
+
+        let encrypted = |cleartext: &str| {
+            let mut encrypted = KeyVariant::new();
+            encrypted.insert("CleartextDevice".into(), object_path(cleartext));
+            encrypted
+        };
+        let cache = cache(vec![
+            (
+                CONTAINER,
+                vec![(BLOCK, block("/")), (encrypted::DEST, encrypted(INNER))],
+            ),
+            (
+                INNER,
+                vec![
+                    (BLOCK, block(CONTAINER)),
+                    (encrypted::DEST, encrypted(CLEARTEXT)),
+                ],
+            ),
+            (CLEARTEXT, vec![(BLOCK, block(INNER))]),
+        ]);
+
+        let outermost = cache
+            .outermost_unlocked()
+            .into_iter()
+            .map(|block| block.path)
+            .collect::<Vec<_>>();
+        assert_eq!(outermost, vec![CONTAINER]);
+        let container = cache.get_block(CONTAINER).unwrap();
+        let stacked = cache
+            .stacked_on(&container)
+            .into_iter()
+            .map(|block| block.path)
+            .collect::<Vec<_>>();
+        assert_eq!(stacked, vec![CLEARTEXT, INNER]);
+    }
+
+    #[test]
+    fn shrinks_encrypted_within_header() {
+        let cache = DiskCache::default();
+        let plan = cache
+            .encrypted_resize_plan(&container(16 << 20), 2 * GIB)
+            .unwrap();
+        assert_eq!(
+            plan,
+            vec![
+                (CONTAINER.into(), encrypted::DEST, 2 * GIB - (16 << 20)),
+                (CONTAINER.into(), partition::DEST, 2 * GIB),
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_shrink_with_unknown_header() {
+        let cache = DiskCache::default();
+        let result = cache.encrypted_resize_plan(&container(0), 2 * GIB);
+        assert!(matches!(result, Err(UdisksError::UnknownMetadataSize(_))));
+    }
+
+    #[test]
+    fn refuses_shrink_into_header() {
+        let cache = DiskCache::default();
+        for &size in &[16 << 20, 1 << 20] {
+            let result = cache.encrypted_resize_plan(&container(16 << 20), size);
+            assert!(matches!(result, Err(UdisksError::SizeTooSmall(_))));
+        }
+    }
 }
@@ -0,0 +1,77 @@
+//! Reading the negotiated speed of a device's link from sysfs.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The negotiated speed of the link between a device and its host, along with the fastest the
+/// link supports where known. Returned by [`Block::link_speed`][crate::Block::link_speed] and
+/// [`Drive::link_speed`][crate::Drive::link_speed].
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum LinkSpeed {
+    /// A SATA link, in Gbps, such as `6.0`. `max_gbps` is the limit of the host port.
+    Sata { gbps: f64, max_gbps: Option<f64> },
+    /// A USB device, in Mbps, such as `480` for USB 2 or `5000` for USB 3.
+    Usb { mbps: u32 },
+    /// A PCIe link, such as of an NVMe drive, in GT/s per lane, along with its lane count.
+    Pcie {
+        gts: f64,
+        width: u32,
+        max_gts: Option<f64>,
+        max_width: Option<u32>,
+    },
+}
+
+/// Find the link speed of the device at the given path in sysfs, such as
+/// `/sys/dev/block/8:0`, by walking up from it towards its host.
+pub(crate) fn link_speed(device: &Path) -> Option<LinkSpeed> {
+    let device = device.canonicalize().ok()?;
+    for dir in device.ancestors() {
+        let name = dir.file_name()?.to_str()?;
+        if let Some(port) = name.strip_prefix("ata") {
+            if port.parse::<u32>().is_ok() {
+                return sata(port);
+            }
+        }
+        if dir.join("idVendor").exists() {
+            let mbps = read(&dir.join("speed"))?.parse::<f64>().ok()?;
+            return Some(LinkSpeed::Usb { mbps: mbps as u32 });
+        }
+        if dir.join("current_link_speed").exists() {
+            return pcie(dir);
+        }
+    }
+    None
+}
+
+/// Read from the first link of the ATA port, as the drive is directly attached unless behind a
+/// port multiplier.
+fn sata(port: &str) -> Option<LinkSpeed> {
+    let link = PathBuf::from(format!("/sys/class/ata_link/link{}", port));
+    Some(LinkSpeed::Sata {
+        gbps: rate(&read(&link.join("sata_spd"))?)?,
+        max_gbps: read(&link.join("hw_sata_spd_limit")).and_then(|limit| rate(&limit)),
+    })
+}
+
+fn pcie(dir: &Path) -> Option<LinkSpeed> {
+    let width = |file| read(&dir.join(file))?.parse::<u32>().ok();
+    Some(LinkSpeed::Pcie {
+        gts: rate(&read(&dir.join("current_link_speed"))?)?,
+        width: width("current_link_width")?,
+        max_gts: read(&dir.join("max_link_speed")).and_then(|speed| rate(&speed)),
+        max_width: width("max_link_width"),
+    })
+}
+
+/// Parse the leading number of a rate such as `"6.0 Gbps"` or `"8.0 GT/s PCIe"`. `None` for
+/// `"<unknown>"`, which is reported for links that are down.
+fn rate(value: &str) -> Option<f64> {
+    value.split_whitespace().next()?.parse().ok()
+}
+
+fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_owned())
+}
@@ -0,0 +1,6 @@
+//! Constants related to loop devices.
+
+pub(crate) const DEST: &str = "org.freedesktop.UDisks2.Loop";
+pub(crate) const SET_AUTOCLEAR: &str = "SetAutoclear";
+pub(crate) const DELETE: &str = "Delete";
+pub(crate) const BACKING_FILE: &str = "BackingFile";
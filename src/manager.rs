@@ -0,0 +1,30 @@
+//! Constants related to the UDisks manager object.
+
+pub(crate) const DEST: &str = "org.freedesktop.UDisks2.Manager";
+pub(crate) const PATH: &str = "/org/freedesktop/UDisks2/Manager";
+pub(crate) const ENABLE_MODULES: &str = "EnableModules";
+pub(crate) const GET_BLOCK_DEVICES: &str = "GetBlockDevices";
+pub(crate) const SUPPORTED_FILESYSTEMS: &str = "SupportedFilesystems";
+pub(crate) const CAN_FORMAT: &str = "CanFormat";
+pub(crate) const CAN_RESIZE: &str = "CanResize";
+pub(crate) const CAN_CHECK: &str = "CanCheck";
+pub(crate) const CAN_REPAIR: &str = "CanRepair";
+pub(crate) const DEFAULT_ENCRYPTION_TYPE: &str = "DefaultEncryptionType";
+/// The encryption type used by daemons which predate `DefaultEncryptionType`.
+pub(crate) const LEGACY_ENCRYPTION_TYPE: &str = "luks1";
+
+/// Whether the error is due to the property not existing on this version of UDisks.
+pub(crate) fn is_missing_property(why: &dbus::Error) -> bool {
+    match why.name() {
+        Some(name) => {
+            name == "org.freedesktop.DBus.Error.InvalidArgs"
+                || name == "org.freedesktop.DBus.Error.UnknownProperty"
+        }
+        None => false,
+    }
+}
+
+/// Whether the error is due to the method not existing on this version of UDisks.
+pub(crate) fn is_missing_method(why: &dbus::Error) -> bool {
+    why.name() == Some("org.freedesktop.DBus.Error.UnknownMethod")
+}
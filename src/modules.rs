@@ -0,0 +1,109 @@
+//! Interfaces of blocks which are only provided by optional UDisks modules.
+
+use crate::utils::*;
+use crate::DbusObjects;
+
+pub(crate) const BCACHE: &str = "org.freedesktop.UDisks2.Block.Bcache";
+pub(crate) const ZRAM: &str = "org.freedesktop.UDisks2.Block.ZRAM";
+
+/// A bcache device, which caches a slower backing device on a faster one, such as an SSD.
+///
+/// This is only provided once the `bcache` module of UDisks has been loaded, such as by
+/// [`enable_modules`][crate::UDisks2::enable_modules]. The properties vary with the version of
+/// the module, so any which are missing are left as their defaults.
+#[derive(Clone, Debug, Default)]
+pub struct Bcache {
+    /// The path of the block which this is an interface of.
+    pub path: String,
+    /// The state of the device, such as `"clean"` or `"dirty"`.
+    pub state: String,
+    /// The caching mode, such as `"writethrough"` or `"writeback"`.
+    pub mode: String,
+    pub block_size: u64,
+    pub cache_size: u64,
+    pub cache_free: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub bypass_hits: u64,
+    pub bypass_misses: u64,
+}
+
+/// A zram device, which is a compressed block device in RAM, typically used for swap.
+///
+/// This is only provided once the `zram` module of UDisks has been loaded, such as by
+/// [`enable_modules`][crate::UDisks2::enable_modules]. The properties vary with the version of
+/// the module, so any which are missing are left as their defaults.
+#[derive(Clone, Debug, Default)]
+pub struct Zram {
+    /// The path of the block which this is an interface of.
+    pub path: String,
+    /// The size of the device, in bytes.
+    pub disksize: u64,
+    /// The compression algorithm, such as `"lzo"` or `"zstd"`.
+    pub comp_algorithm: String,
+    pub max_comp_streams: u64,
+    /// Bytes stored in the device, before compression.
+    pub orig_data_size: u64,
+    /// Bytes stored in the device, after compression.
+    pub compr_data_size: u64,
+    /// Bytes of memory used by the device, including overhead.
+    pub mem_used_total: u64,
+    pub num_reads: u64,
+    pub num_writes: u64,
+    /// Whether the device is active as swap.
+    pub active: bool,
+}
+
+impl ParseFrom for Bcache {
+    fn parse_from(path: &str, objects: &DbusObjects) -> Option<Bcache> {
+        let object = objects.get(BCACHE)?;
+        let mut bcache = Bcache {
+            path: path.to_owned(),
+            ..Default::default()
+        };
+        for (key, value) in object {
+            match key.as_str() {
+                "State" => bcache.state = get_string(value).unwrap_or_default(),
+                "Mode" => bcache.mode = get_string(value).unwrap_or_default(),
+                "BlockSize" => bcache.block_size = get_u64(value),
+                "CacheSize" => bcache.cache_size = get_u64(value),
+                "CacheFree" => bcache.cache_free = get_u64(value),
+                "Hits" => bcache.hits = get_u64(value),
+                "Misses" => bcache.misses = get_u64(value),
+                "BypassHits" => bcache.bypass_hits = get_u64(value),
+                "BypassMisses" => bcache.bypass_misses = get_u64(value),
+                _ => {
+                    log::debug!("unhandled {}::{}", BCACHE, key);
+                }
+            }
+        }
+        Some(bcache)
+    }
+}
+
+impl ParseFrom for Zram {
+    fn parse_from(path: &str, objects: &DbusObjects) -> Option<Zram> {
+        let object = objects.get(ZRAM)?;
+        let mut zram = Zram {
+            path: path.to_owned(),
+            ..Default::default()
+        };
+        for (key, value) in object {
+            match key.as_str() {
+                "disksize" => zram.disksize = get_u64(value),
+                "comp_algorithm" => zram.comp_algorithm = get_string(value).unwrap_or_default(),
+                "max_comp_streams" => zram.max_comp_streams = get_u64(value),
+                "orig_data_size" => zram.orig_data_size = get_u64(value),
+                "compr_data_size" => zram.compr_data_size = get_u64(value),
+                "mem_used_total" => zram.mem_used_total = get_u64(value),
+                "num_reads" => zram.num_reads = get_u64(value),
+                "num_writes" => zram.num_writes = get_u64(value),
+                "active" => zram.active = get_bool(value),
+                _ => {
+                    log::debug!("unhandled {}::{}", ZRAM, key);
+                }
+            }
+        }
+        Some(zram)
+    }
+}
@@ -1,16 +1,64 @@
-use crate::smart::{RawSmartAttribute, SmartData, SmartStatus, SmartValue};
+use crate::filesystem::{FsCapabilities, MountOptions, UnmountOptions};
+use crate::format::FormatOptions;
+use crate::options::{options_builder, Interactivity, Wakeup};
+use crate::partition::PartitionOptions;
+use crate::power::PowerState;
+use crate::smart::{DriveHealth, RawSmartAttribute, SmartData, SmartStatus, SmartValue};
 use crate::utils::KeyVariant;
-use crate::{smart, Block, DiskCache, Drive, DEST, NO_WAKEUP, PATH};
+use crate::utils::{get_array_of_byte_arrays, get_bool};
+use crate::{
+    drive, encrypted, filesystem, format, job, loop_device, manager, partition, polkit, power,
+    smart, Bcache, Block, DbusObjects, DeviceSpec, DiskCache, DiskDevice, Disks, Drive, Job,
+    Result, UdisksAction, UdisksError, Zram, DEACTIVATE, DEFAULT_TIMEOUT, DEST, LOGICAL_VOLUME,
+    PATH, RESIZE, SIZE,
+};
 use dbus::arg::Variant;
+use dbus::channel::{MatchingReceiver, Sender};
+use dbus::message::SignalArgs;
 use dbus::nonblock;
 use dbus::nonblock::stdintf::org_freedesktop_dbus::{ObjectManager, Properties};
+use dbus::nonblock::stdintf::org_freedesktop_dbus::{
+    ObjectManagerInterfacesRemoved, PropertiesPropertiesChanged,
+};
 use dbus::nonblock::NonblockReply;
+use dbus::Message;
+use futures_util::future::{join_all, select, try_join_all, Either};
 use futures_util::join;
+use futures_util::{future, stream, Stream, StreamExt};
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
 use std::ops::Deref;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// How often [`AsyncUDisks2::await_power_state`] checks the power mode of a drive.
+const POWER_STATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// All disks from the cache, along with the S.M.A.R.T. data of their drives.
+///
+/// Returned by [`AsyncUDisks2::full_snapshot`].
+#[derive(Debug, Default, Clone)]
+pub struct FullSnapshot {
+    pub devices: Vec<FullDevice>,
+}
+
+/// A [`DiskDevice`] along with the S.M.A.R.T. data of its drive.
+#[derive(Debug, Default, Clone)]
+pub struct FullDevice {
+    pub device: DiskDevice,
+    /// `None` if the S.M.A.R.T. data could not be read, such as for drives without the
+    /// `org.freedesktop.UDisks2.Drive.Ata` interface.
+    pub smart: Option<SmartValue>,
+}
 
 /// Async version of [`UDisks2`][crate::UDisks2].
 ///
+/// Dropping the future of a method call does not cancel the operation on the UDisks server,
+/// which carries on as a job. Use [`cancel_job`][Self::cancel_job] to stop it.
+///
 /// This requires enabling the `futures` feature flag:
 /// ```toml
 /// [dependencies]
@@ -19,6 +67,70 @@ use std::time::Duration;
 pub struct AsyncUDisks2<C> {
     conn: C,
     pub(crate) cache: DiskCache,
+    timeout: Duration,
+    retries: u32,
+}
+
+/// Configures and builds an [`AsyncUDisks2`]. Async version of
+/// [`UDisks2Builder`][crate::UDisks2Builder].
+pub struct AsyncUDisks2Builder<C> {
+    conn: C,
+    timeout: Duration,
+    retries: u32,
+    update: bool,
+}
+
+impl<C, T> AsyncUDisks2Builder<C>
+where
+    T: NonblockReply,
+    C: Deref<Target = T>,
+{
+    /// Start configuring an [`AsyncUDisks2`] which uses the given connection, such as one from
+    /// `dbus_tokio::connection::new_system_sync`. There is no system bus default, as the
+    /// connection must be driven by the caller's runtime.
+    pub fn new(conn: C) -> Self {
+        Self {
+            conn,
+            timeout: DEFAULT_TIMEOUT,
+            retries: 0,
+            update: true,
+        }
+    }
+
+    /// The timeout of method calls and property reads which don't take one explicitly. Defaults
+    /// to 3 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How many times [`update`][AsyncUDisks2::update] retries fetching the managed objects
+    /// after a failure. Defaults to 0.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Whether to [`update`][AsyncUDisks2::update] the cache when building. Defaults to `true`;
+    /// if `false`, the cache is empty until the first `update`.
+    pub fn update_on_build(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    pub async fn build(self) -> Result<AsyncUDisks2<C>, dbus::Error> {
+        let mut udisks2 = AsyncUDisks2 {
+            conn: self.conn,
+            cache: Default::default(),
+            timeout: self.timeout,
+            retries: self.retries,
+        };
+
+        if self.update {
+            udisks2.update().await?;
+        }
+        Ok(udisks2)
+    }
 }
 
 impl<'b, C, T> AsyncUDisks2<C>
@@ -26,6 +138,8 @@ where
     T: NonblockReply + 'b,
     C: Deref<Target = T>,
 {
+    /// Fetch the managed objects with the default options of [`AsyncUDisks2Builder`].
+    ///
     /// ```
     /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
     /// // Connect to the D-Bus session bus (this is blocking, unfortunately).
@@ -42,25 +156,145 @@ where
     /// # });
     /// ```
     pub async fn new(conn: C) -> Result<Self, dbus::Error> {
-        let mut udisks2 = Self {
-            conn,
-            cache: Default::default(),
-        };
-
-        udisks2.update().await?;
-        Ok(udisks2)
+        AsyncUDisks2Builder::new(conn).build().await
     }
 
     fn proxy<'a>(&'a self, path: impl Into<dbus::Path<'a>>) -> nonblock::Proxy<&T> {
-        nonblock::Proxy::new(DEST, path, Duration::from_millis(3000), &self.conn)
+        self.proxy_with_timeout(path, self.timeout)
+    }
+
+    fn proxy_with_timeout<'a>(
+        &'a self,
+        path: impl Into<dbus::Path<'a>>,
+        timeout: Duration,
+    ) -> nonblock::Proxy<'a, &'a T> {
+        nonblock::Proxy::new(DEST, path, timeout, &self.conn)
     }
 
     /// Refresh the managed objects fetched from the DBus server.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. The cache is replaced in a single step once the complete
+    /// reply has arrived, so dropping the future before it completes, such as in a losing branch
+    /// of `tokio::select!`, leaves the previous cache untouched. A reply arriving after the
+    /// future is dropped is discarded.
     pub async fn update(&mut self) -> Result<(), dbus::Error> {
-        self.cache.0 = self.proxy(PATH).get_managed_objects().await?;
+        let mut attempts = 0;
+        loop {
+            match self.proxy(PATH).get_managed_objects().await {
+                Ok(objects) => {
+                    self.cache.0 = objects;
+                    return Ok(());
+                }
+                Err(_) if attempts < self.retries => attempts += 1,
+                Err(why) => return Err(why),
+            }
+        }
+    }
+
+    /// Cheaply check whether block devices were added or removed since the last
+    /// [`update`][Self::update], by comparing the paths returned by the manager's
+    /// `GetBlockDevices` against the cache. A polling loop may use this to skip updates when
+    /// nothing changed.
+    ///
+    /// Only the set of block paths is compared. Changes to the properties of existing blocks,
+    /// such as mount points, labels, or sizes, are not detected, nor are drives without any
+    /// blocks, such as an empty card reader, coming and going.
+    pub async fn has_changes(&self) -> Result<bool, dbus::Error> {
+        let options = options_builder().build();
+        let (paths,): (Vec<dbus::Path<'static>>,) = self
+            .proxy(manager::PATH)
+            .method_call(manager::DEST, manager::GET_BLOCK_DEVICES, (options,))
+            .await?;
+        Ok(self.cache.block_paths_differ(&paths))
+    }
+
+    /// Refresh only the sizes of the cached blocks and partitions, which is much cheaper than a
+    /// full [`update`][Self::update] after resizing. Any other changes, such as added or removed
+    /// devices, still require an `update`.
+    ///
+    /// Like `update`, this is cancel safe: the sizes are only written to the cache once all of
+    /// them have been read.
+    pub async fn refresh_sizes(&mut self) -> Result<(), dbus::Error> {
+        let sized = self.cache.sized_interfaces();
+        let sizes = try_join_all(
+            sized
+                .iter()
+                .map(|(path, interface)| self.proxy(path.clone()).get::<u64>(interface, SIZE)),
+        )
+        .await?;
+        for ((path, interface), size) in sized.iter().zip(sizes) {
+            self.cache.set_size(path, interface, size);
+        }
+        Ok(())
+    }
+
+    /// Re-read every property of a single cached object, such as a block, which is much cheaper
+    /// than a full [`update`][Self::update]. Objects which aren't cached are ignored.
+    ///
+    /// UDisks only re-reads a block's state, such as `read_only`, when the kernel sends a uevent
+    /// for it. Toggling read-only with `blockdev --setro` sends none, so UDisks, and this, may
+    /// report the old value until the next change event. UDisks has no method for setting a
+    /// block read-only; that must be done through the kernel.
+    pub async fn refresh_block(&mut self, path: &str) -> Result<(), dbus::Error> {
+        let interfaces = self.cache.interfaces_of(path);
+        let properties = try_join_all(
+            interfaces
+                .iter()
+                .map(|interface| self.proxy(path).get_all(interface)),
+        )
+        .await?;
+        for (interface, properties) in interfaces.into_iter().zip(properties) {
+            self.cache.set_interface(path, interface, properties);
+        }
         Ok(())
     }
 
+    /// Refresh a block in the cache after changing it, and return it. The whole cache is updated
+    /// if the block wasn't cached yet.
+    async fn refreshed_block(&mut self, path: &str) -> Result<Block> {
+        if self.cache.interfaces_of(path).is_empty() {
+            self.update().await?;
+        } else {
+            self.refresh_block(path).await?;
+        }
+        self.get_block(path)
+            .ok_or_else(|| UdisksError::NoSuchBlock(path.to_owned()))
+    }
+
+    /// Read a block afresh from UDisks after changing it, and return it, leaving the cache as it
+    /// was.
+    async fn read_block(&self, path: &str) -> Result<Block> {
+        let objects = self.proxy(PATH).get_managed_objects().await?;
+        DiskCache(objects)
+            .get_block(path)
+            .ok_or_else(|| UdisksError::NoSuchBlock(path.to_owned()))
+    }
+
+    /// Read a drive afresh from UDisks, without a full [`update`][Self::update], such as for a
+    /// detail view refreshed on a timer. Only its `org.freedesktop.UDisks2.Drive` interface is
+    /// read, and the cache is left as it was.
+    ///
+    /// Returns [`UdisksError::NoSuchDrive`] if the drive no longer exists.
+    pub async fn refresh_drive(&self, drive: &Drive) -> Result<Drive> {
+        let properties = match self.proxy(drive).get_all(drive::DEST).await {
+            Ok(properties) => properties,
+            // GDBus reports a removed object as an unknown method, and a removed interface as
+            // an invalid argument.
+            Err(ref why)
+                if manager::is_missing_property(why) || manager::is_missing_method(why) =>
+            {
+                return Err(UdisksError::NoSuchDrive(drive.path.clone()))
+            }
+            Err(why) => return Err(why.into()),
+        };
+        let mut objects = DbusObjects::new();
+        objects.insert(drive::DEST.to_owned(), properties);
+        Drive::from_dbus(&drive.path, &objects)
+            .ok_or_else(|| UdisksError::NoSuchDrive(drive.path.clone()))
+    }
+
     /// Find the drive that corresponds to the given dbus object path.
     pub fn get_drive(&self, path: &str) -> Option<Drive> {
         self.cache.get_drive(path)
@@ -71,28 +305,202 @@ where
         self.cache.get_drives()
     }
 
+    /// Other drives which are the same physical device as the given drive, reached through
+    /// another path, such as with multipath. These share its `sibling_id`. Returns nothing if
+    /// the drive has no `sibling_id`.
+    pub fn sibling_drives(&self, drive: &Drive) -> Vec<Drive> {
+        self.cache.sibling_drives(drive)
+    }
+
+    /// Drives with removable media but no media present, such as an empty card reader slot or
+    /// optical drive. These have no blocks, so they are absent from [`Disks`].
+    pub fn get_empty_drives<'a>(&'a self) -> impl Iterator<Item = Drive> + 'a {
+        self.cache
+            .get_drives()
+            .filter(|drive| drive.media_removable && !drive.media_available)
+    }
+
+    /// Blocks with a mountable file system on removable drives, sorted by device. This is the
+    /// set of removable media which a file manager would list.
+    ///
+    /// Both partitions and whole-disk blocks are included, as removable media such as USB
+    /// sticks are often formatted without a partition table (a "superfloppy"), with the file
+    /// system directly on the whole disk. Blocks holding a partition table are excluded.
+    pub fn removable_filesystems(&self) -> Vec<Block> {
+        self.cache.removable_filesystems()
+    }
+
+    /// The bcache devices in the cache. These require the `bcache` module of UDisks, which may
+    /// be loaded with [`enable_modules`][Self::enable_modules] followed by an
+    /// [`update`][Self::update].
+    pub fn get_bcache_devices<'a>(&'a self) -> impl Iterator<Item = Bcache> + 'a {
+        self.cache.get_bcache_devices()
+    }
+
+    /// The zram devices in the cache. These require the `zram` module of UDisks, which may be
+    /// loaded with [`enable_modules`][Self::enable_modules] followed by an
+    /// [`update`][Self::update].
+    pub fn get_zram_devices<'a>(&'a self) -> impl Iterator<Item = Zram> + 'a {
+        self.cache.get_zram_devices()
+    }
+
+    /// An iterator of the running jobs in the cache.
+    pub fn get_jobs<'a>(&'a self) -> impl Iterator<Item = Job> + 'a {
+        self.cache.get_jobs()
+    }
+
+    /// The running jobs in the cache which affect the object at the given dbus object path,
+    /// such as a block being formatted.
+    pub fn jobs_of(&self, path: &str) -> Vec<Job> {
+        self.cache.jobs_of(path)
+    }
+
     /// Find the block that corresponds to the given dbus object path.
     pub fn get_block(&self, path: &str) -> Option<Block> {
         self.cache.get_block(path)
     }
 
+    /// Find the block with the given device node, such as `/dev/sda1`. The preferred device
+    /// node and symlinks of blocks are matched too, such as `/dev/mapper/cryptroot` or
+    /// `/dev/disk/by-uuid/...`.
+    pub fn get_block_by_device(&self, device: &Path) -> Option<Block> {
+        self.cache.get_block_by_device(device)
+    }
+
+    /// Find the block referred to by a device specification, as found in `/etc/fstab`, such as
+    /// `UUID=...` or `/dev/sda1`. See [`DeviceSpec::parse`] for the supported forms.
+    pub fn resolve_device(&self, spec: &DeviceSpec) -> Option<Block> {
+        self.cache.resolve_device(spec)
+    }
+
+    /// Find the whole-disk block, which holds the partition table, that the given partition
+    /// belongs to. Returns `None` if the block isn't a partition.
+    pub fn whole_disk_of(&self, block: &Block) -> Option<Block> {
+        self.cache.whole_disk_of(block)
+    }
+
+    /// Every block belonging to the drive: the whole disk, its partitions sorted by number, and
+    /// then the cleartext blocks of any unlocked containers among them. This is the raw grouping
+    /// from the cache, before [`Disks`] splits it into parents and partitions.
+    ///
+    /// Blocks are matched by their `drive`, except for cleartext blocks, which UDisks does not
+    /// associate with a drive, and so are matched by their `crypto_backing_device`.
+    pub fn blocks_of_drive(&self, drive: &Drive) -> Vec<Block> {
+        self.cache.blocks_of_drive(drive)
+    }
+
     /// An iterator of `Block` objects fetched from the inner cached managed objects.
     pub fn get_blocks<'a>(&'a self) -> impl Iterator<Item = Block> + 'a {
         self.cache.get_blocks()
     }
 
+    /// The names of interfaces found on the cached objects which this crate doesn't parse, such
+    /// as `org.freedesktop.UDisks2.MDRaid`, or those of modules added by newer versions of
+    /// UDisks. Their properties are still available through [`raw_objects`][Self::raw_objects].
+    ///
+    /// The standard `org.freedesktop.DBus` interfaces are left out. Properties which are not
+    /// parsed on handled interfaces are not covered, and are instead logged at the debug level
+    /// while parsing.
+    pub fn unhandled_interfaces(&self) -> BTreeSet<String> {
+        self.cache.unhandled_interfaces()
+    }
+
+    /// Every block which isn't a component of another device, such as a RAID member or an
+    /// unlocked encrypted container. See [`Block::is_component`] for the criteria.
+    pub fn get_mountable_blocks<'a>(&'a self) -> impl Iterator<Item = Block> + 'a {
+        self.get_blocks().filter(|block| !block.is_component())
+    }
+
+    /// Find the block which is mounted at the given path, such as `/` for the root file system.
+    ///
+    /// The path is canonicalized first if possible, so that relative paths and paths through
+    /// symlinks are resolved. It must be a mount point itself, rather than a path within one.
+    ///
+    /// If no mount point matches exactly, the mount points are canonicalized too, to find file
+    /// systems mounted at paths which have since become symlinks. This touches the file system
+    /// for every mount point, which may block on unresponsive network mounts.
+    pub fn block_for_mount(&self, path: &Path) -> Option<Block> {
+        self.cache.block_for_mount(path)
+    }
+
+    /// Guess whether mounting the block will require authentication, following the default
+    /// polkit policy of UDisks: devices considered to be system devices, which includes any block
+    /// of a non-removable drive, require administrator authentication, while others may be
+    /// mounted by users of an active local session without it.
+    ///
+    /// This is a heuristic. Local polkit rules, inactive or remote sessions, and devices attached
+    /// to other seats may change the outcome, which is only known for certain by attempting the
+    /// mount.
+    pub fn mount_requires_auth(&self, block: &Block) -> bool {
+        block.hint_system
+            || matches!(self.cache.get_drive(&block.drive), Some(drive) if !drive.removable)
+    }
+
+    /// Every unlocked encrypted block, paired with the cleartext block it is mapped to, as
+    /// `(container, cleartext)`. Locked containers are skipped.
+    pub fn unlocked_pairs(&self) -> Vec<(Block, Block)> {
+        self.cache.unlocked_pairs()
+    }
+
+    /// Read a property of a UDisks object directly over D-Bus, for properties which this crate
+    /// does not model. The cache is neither used nor updated.
+    ///
+    /// `V` must match the D-Bus signature of the property, such as `u64` for `t`, or
+    /// `dbus::Path<'static>` for `o`, or the call fails.
+    pub async fn get_property<V: for<'z> dbus::arg::Get<'z> + 'static>(
+        &self,
+        path: &str,
+        interface: &str,
+        name: &str,
+    ) -> Result<V, dbus::Error> {
+        self.proxy(path).get(interface, name).await
+    }
+
+    /// Write a property of a UDisks object directly over D-Bus, for properties which this crate
+    /// does not model. Call [`update`][Self::update] afterwards to see the change in the cache.
+    ///
+    /// `V` must match the D-Bus signature of the property, or the call fails.
+    pub async fn set_property<V: dbus::arg::Arg + dbus::arg::Append>(
+        &self,
+        path: &str,
+        interface: &str,
+        name: &str,
+        value: V,
+    ) -> Result<(), dbus::Error> {
+        self.proxy(path).set(interface, name, value).await
+    }
+
+    /// The cached managed objects, as returned by UDisks, keyed by their object paths. Each
+    /// object maps its interfaces to their properties.
+    ///
+    /// This is intended for debugging, and for reaching interfaces and properties which this
+    /// crate does not parse. Prefer the parsed getters, such as [`get_blocks`][Self::get_blocks].
+    pub fn raw_objects(&self) -> &HashMap<dbus::Path<'static>, DbusObjects> {
+        &self.cache.0
+    }
+
+    /// Parse all drives and blocks from the cache at once.
+    ///
+    /// Both are parsed from the same cached state. As the cache is only ever replaced through
+    /// `&mut self`, such as by [`update`][Self::update], no update can be applied in between, so
+    /// the blocks always refer to the drives of the same snapshot.
+    pub fn snapshot(&self) -> (Vec<Drive>, Vec<Block>) {
+        (
+            self.cache.get_drives().collect(),
+            self.cache.get_blocks().collect(),
+        )
+    }
+
     /// Update the S.M.A.R.T. attributes of a drive. You may pass either a `&`[`Drive`] or `&str`
     /// which is a path to a drive, starting with `/org/freedesktop/UDisks2/drives/`.
     pub async fn smart_update(
         &'b self,
         drive: impl Into<dbus::Path<'b>>,
-        allow_wakeup: bool,
+        wakeup: Wakeup,
     ) -> Result<(), dbus::Error> {
         let proxy = self.proxy(drive);
         let mut options = KeyVariant::<&str>::new();
-        if !allow_wakeup {
-            options.insert(NO_WAKEUP, Variant(Box::new(true)));
-        }
+        wakeup.apply(&mut options);
         proxy
             .method_call(smart::DEST, smart::UPDATE, (options,))
             .await
@@ -103,9 +511,72 @@ where
     pub async fn smart_attributes(
         &'b self,
         drive: impl Into<dbus::Path<'b>>,
-        allow_wakeup: bool,
+        wakeup: Wakeup,
     ) -> Result<SmartValue, dbus::Error> {
-        let proxy = self.proxy(drive);
+        self.smart_attributes_with_timeout(drive, wakeup, self.timeout)
+            .await
+    }
+
+    /// Get the S.M.A.R.T. attributes of every ATA drive, reading at most `concurrency` drives at
+    /// a time, with a timeout of `per_timeout` for each drive. The results are returned in the
+    /// order the drives finished in.
+    ///
+    /// Limiting the concurrency staggers the reads on systems with many drives, rather than
+    /// flooding the bus, and, with [`Wakeup::Allow`], waking every drive at once.
+    pub async fn smart_attributes_all_limited(
+        &'b self,
+        wakeup: Wakeup,
+        concurrency: usize,
+        per_timeout: Duration,
+    ) -> Vec<(Drive, Result<SmartValue, dbus::Error>)> {
+        let drives = self
+            .get_drives()
+            .filter(|drive| self.cache.has_interface(&drive.path, smart::DEST))
+            .collect::<Vec<_>>();
+        stream::iter(drives)
+            .map(|drive| async move {
+                let result = self
+                    .smart_attributes_with_timeout(drive.path.clone(), wakeup, per_timeout)
+                    .await;
+                (drive, result)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Every drive along with its health, read concurrently, with failing drives first: sorted
+    /// by [`DriveHealth`] from `Failing` to `Healthy`, then by `sort_key`. Drives which lack
+    /// S.M.A.R.T. support, such as USB sticks, or whose data could not be read are `Unknown`.
+    ///
+    /// Reading the attributes may wake drives in standby unless `wakeup` is [`Wakeup::Prevent`],
+    /// in which case sleeping drives report the data of their last update. Drives whose data has
+    /// never been updated are `Unknown`, and are not updated by this.
+    pub async fn drives_by_health(&'b self, wakeup: Wakeup) -> Vec<(Drive, DriveHealth)> {
+        let mut drives = self.get_drives().collect::<Vec<_>>();
+        drives.sort_by(|a, b| a.sort_key.cmp(&b.sort_key));
+        let healths = join_all(drives.iter().map(|drive| async move {
+            if !self.cache.has_interface(&drive.path, smart::DEST) {
+                return DriveHealth::Unknown;
+            }
+            match self.smart_attributes(&drive.path, wakeup).await {
+                Ok(value) => value.health(),
+                Err(_) => DriveHealth::Unknown,
+            }
+        }))
+        .await;
+        let mut drives = drives.into_iter().zip(healths).collect::<Vec<_>>();
+        drives.sort_by_key(|&(_, health)| health);
+        drives
+    }
+
+    async fn smart_attributes_with_timeout(
+        &'b self,
+        drive: impl Into<dbus::Path<'b>>,
+        wakeup: Wakeup,
+        timeout: Duration,
+    ) -> Result<SmartValue, dbus::Error> {
+        let proxy = self.proxy_with_timeout(drive, timeout);
         if !proxy.get::<bool>(smart::DEST, smart::SUPPORTED).await? {
             return Ok(SmartValue::NotSupported);
         }
@@ -117,9 +588,7 @@ where
             return Ok(SmartValue::NotUpdated);
         }
         let mut options = KeyVariant::<&str>::new();
-        if !allow_wakeup {
-            options.insert(NO_WAKEUP, Variant(Box::new(true)));
-        }
+        wakeup.apply(&mut options);
         let (attrs,): (Vec<RawSmartAttribute>,) = proxy
             .method_call(smart::DEST, smart::GET_ATTRS, (options,))
             .await?;
@@ -131,6 +600,7 @@ where
             past_failing_attrs_count,
             bad_sectors,
             status,
+            selftest_percent_remaining,
         ) = join!(
             proxy.get(smart::DEST, smart::FAILING),
             proxy.get(smart::DEST, smart::TIME_POWER_ON),
@@ -139,6 +609,7 @@ where
             proxy.get(smart::DEST, smart::PAST_FAILING_ATTRS_COUNT),
             proxy.get(smart::DEST, smart::BAD_SECTORS),
             proxy.get::<String>(smart::DEST, smart::STATUS),
+            proxy.get(smart::DEST, smart::PERCENT_REMAINING),
         );
         Ok(SmartValue::Enabled(SmartData {
             updated,
@@ -149,7 +620,1039 @@ where
             past_failing_attrs_count: past_failing_attrs_count?,
             bad_sectors: bad_sectors?,
             status: status?.parse().unwrap_or(SmartStatus::Unknown),
+            selftest_percent_remaining: Some(selftest_percent_remaining?)
+                .filter(|percent| *percent >= 0),
             attributes: attrs.into_iter().map(Into::into).collect(),
         }))
     }
+
+    /// Unmount the file system of a block. You may pass either a `&`[`Block`] or `&str` which is
+    /// a path to a block, starting with `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    ///
+    /// UDisks replies once the file system is unmounted, after flushing its data, which may take
+    /// long for slow devices. See [`unmount_no_wait`][Self::unmount_no_wait] to return
+    /// immediately.
+    pub async fn unmount(
+        &'b self,
+        block: impl Into<dbus::Path<'b>>,
+        options: &UnmountOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let proxy = self.proxy_with_timeout(block, timeout);
+        proxy
+            .method_call(
+                filesystem::DEST,
+                filesystem::UNMOUNT,
+                (options.build(interactive.into()),),
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Mount the file system of a block, returning the path it was mounted at along with the
+    /// block. You may pass either a `&`[`Block`] or `&str` which is a path to a block, starting
+    /// with `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// Only the `MountPoints` of the block are read back and patched into the cache, which is
+    /// much cheaper than an [`update`][Self::update], and leaves other cached objects as they
+    /// were. The returned block reflects the new mount point.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn mount<'a>(
+        &mut self,
+        block: impl Into<dbus::Path<'a>>,
+        options: &MountOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<(PathBuf, Block)> {
+        let block = block.into();
+        let proxy = self.proxy_with_timeout(block.clone(), timeout);
+        let (mount_path,): (String,) = proxy
+            .method_call(
+                filesystem::DEST,
+                filesystem::MOUNT,
+                (options.build(interactive.into()),),
+            )
+            .await?;
+        let mount_points = proxy
+            .get(filesystem::DEST, filesystem::MOUNT_POINTS)
+            .await?;
+        self.cache.set_mount_points(&block, mount_points);
+        let block = self
+            .get_block(&block)
+            .ok_or_else(|| UdisksError::NoSuchBlock(block.to_string()))?;
+        Ok((PathBuf::from(mount_path), block))
+    }
+
+    /// Format a block with a new file system of the type `fstype`, such as `"ext4"`, or
+    /// `"empty"` to only wipe existing signatures. You may pass either a `&`[`Block`] or `&str`
+    /// which is a path to a block, starting with `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// The block is read back from UDisks and returned, so that its `id_type`, `id_label` and
+    /// `id_uuid` describe the new file system. With [`no_block`][FormatOptions::no_block], UDisks
+    /// may not have probed the new file system by then. As with [`unmount`][Self::unmount], the
+    /// cache is left as it was; call [`update`][Self::update] to refresh it.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn format<'a>(
+        &self,
+        block: impl Into<dbus::Path<'a>>,
+        fstype: &str,
+        options: &FormatOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<Block> {
+        let block = block.into();
+        self.proxy_with_timeout(block.clone(), timeout)
+            .method_call::<(), _, _, _>(
+                format::DEST,
+                format::FORMAT,
+                (fstype, options.build(interactive.into())),
+            )
+            .await?;
+        self.read_block(&block).await
+    }
+
+    /// Create a partition in the partition table of a block, returning the new partition. You
+    /// may pass either a `&`[`Block`] or `&str` which is a path to a block, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// With [`aligned`][PartitionOptions::aligned], the offset and size are first aligned to
+    /// [`DEFAULT_ALIGNMENT`][partition::DEFAULT_ALIGNMENT], as described by
+    /// [`align_region`][partition::align_region]. If no aligned space remains,
+    /// [`UdisksError::RegionTooSmall`] is returned, rather than passing a size of zero, which
+    /// UDisks takes to mean as large as possible. A `size` of zero is passed on as is, to fill
+    /// the free space from the aligned offset.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn create_partition<'a>(
+        &mut self,
+        table: impl Into<dbus::Path<'a>>,
+        offset: u64,
+        size: u64,
+        options: &PartitionOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<Block> {
+        let table = table.into();
+        let (offset, size) = options
+            .region(offset, size)
+            .ok_or_else(|| UdisksError::RegionTooSmall(table.to_string()))?;
+        let (partition,): (dbus::Path<'static>,) = self
+            .proxy_with_timeout(table, timeout)
+            .method_call(
+                partition::TABLE_DEST,
+                partition::CREATE_PARTITION,
+                (
+                    offset,
+                    size,
+                    options.type_.as_str(),
+                    options.name.as_str(),
+                    options.build(interactive.into()),
+                ),
+            )
+            .await?;
+        self.refreshed_block(&partition).await
+    }
+
+    /// Format the file system of a partition in place, keeping its entry in the partition table.
+    /// The partition type is updated to match the new file system.
+    ///
+    /// Unlike [`format`][Self::format], this refuses blocks which aren't partitions, or which
+    /// contain a partition table, so it can never wipe a whole disk. Extended partitions are
+    /// refused too, with [`UdisksError::ExtendedPartition`], as formatting one would wipe the
+    /// logical partitions within it. As with `format`, the partition is read back and returned.
+    pub async fn reformat_partition(
+        &self,
+        partition: &Block,
+        fstype: &str,
+        options: &FormatOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<Block> {
+        if partition.partition.is_none() || partition.table.is_some() {
+            return Err(UdisksError::NotAPartition(partition.path.clone()));
+        }
+        if matches!(partition.partition, Some(ref p) if p.is_container) {
+            return Err(UdisksError::ExtendedPartition(partition.path.clone()));
+        }
+        let proxy = self.proxy_with_timeout(partition, timeout);
+        let mut options = options.build(interactive.into());
+        options.insert(format::UPDATE_PARTITION_TYPE, Variant(Box::new(true)));
+        proxy
+            .method_call::<(), _, _, _>(format::DEST, format::FORMAT, (fstype, options))
+            .await?;
+        self.read_block(&partition.path).await
+    }
+
+    /// Build the [`Disks`] of the cache, and concurrently fetch the S.M.A.R.T. data of every
+    /// drive. Call [`update`][Self::update] first to get the current state.
+    pub async fn full_snapshot(&'b self, wakeup: Wakeup) -> FullSnapshot {
+        let disks = Disks::new_async(self);
+        let smart = join_all(
+            disks
+                .devices
+                .iter()
+                .map(|device| self.smart_attributes(device.drive.path.clone(), wakeup)),
+        )
+        .await;
+        FullSnapshot {
+            devices: disks
+                .devices
+                .into_iter()
+                .zip(smart)
+                .map(|(device, smart)| FullDevice {
+                    device,
+                    smart: smart.ok(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Set whether a loop device is detached automatically once it is no longer in use. You may
+    /// pass either a `&`[`Block`] or `&str` which is a path to a loop device, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn loop_set_autoclear(
+        &'b self,
+        block: impl Into<dbus::Path<'b>>,
+        value: bool,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let block = block.into();
+        if !self.cache.has_interface(&block, loop_device::DEST) {
+            return Err(UdisksError::NotALoopDevice(block.to_string()));
+        }
+        let proxy = self.proxy_with_timeout(block, timeout);
+        let options = options_builder().interactive(interactive).build();
+        proxy
+            .method_call(
+                loop_device::DEST,
+                loop_device::SET_AUTOCLEAR,
+                (value, options),
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Lock an unlocked encrypted block, removing its cleartext block. You may pass either a
+    /// `&`[`Block`] or `&str` which is a path to a block, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`. The cleartext block must not be in use.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn lock(
+        &'b self,
+        block: impl Into<dbus::Path<'b>>,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let proxy = self.proxy_with_timeout(block, timeout);
+        let options = options_builder().interactive(interactive).build();
+        proxy
+            .method_call(encrypted::DEST, encrypted::LOCK, (options,))
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Resize the LUKS container of an unlocked encrypted block, such that its cleartext block
+    /// is `size` bytes, or fills the block if `size` is `0`. The block itself must already be
+    /// large enough; see [`resize_encrypted_stack`][Self::resize_encrypted_stack] for resizing
+    /// a partition along with its contents.
+    ///
+    /// LUKS2 containers may require a `passphrase` to unlock a keyslot for resizing. It is only
+    /// passed on to UDisks, and never logged.
+    ///
+    /// Returns [`UdisksError::NotEncrypted`] if the block is not encrypted.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn resize_encrypted(
+        &'b self,
+        block: &Block,
+        size: u64,
+        passphrase: Option<&str>,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        if block.encrypted.is_none() {
+            return Err(UdisksError::NotEncrypted(block.path.clone()));
+        }
+        let mut options = options_builder().interactive(interactive);
+        if let Some(passphrase) = passphrase {
+            options = options.insert(encrypted::PASSPHRASE, passphrase.to_owned());
+        }
+        self.proxy_with_timeout(block, timeout)
+            .method_call(encrypted::DEST, RESIZE, (size, options.build()))
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Resize an encrypted partition along with the LUKS container and file system within it, in
+    /// the order which keeps the data intact: when growing, the partition, then the container,
+    /// then the file system, each filling the space of the one it's within; when shrinking, the
+    /// file system and container down to `new_size` less the LUKS header, then the partition.
+    ///
+    /// The container must be unlocked, so that its cleartext block can be resized, and a file
+    /// system on it must support resizing in its current state, which for some types means
+    /// unmounted. A cleartext block without a file system, such as an LVM physical volume, is
+    /// left for the caller. LUKS2 containers may require a passphrase to resize, which this does
+    /// not provide; in that case, resize the container with
+    /// [`resize_encrypted`][Self::resize_encrypted].
+    ///
+    /// Returns [`UdisksError::NotAPartition`], [`UdisksError::NotEncrypted`] or
+    /// [`UdisksError::Locked`] before making any changes if `container` is not suitable. When
+    /// shrinking, [`UdisksError::UnknownMetadataSize`] is returned if UDisks doesn't report the
+    /// size of the LUKS header, and [`UdisksError::SizeTooSmall`] if `new_size` leaves no room
+    /// after it. A
+    /// failure part way through leaves the layers at different sizes, but consistent, so the
+    /// operation may be retried. The cached sizes are not updated; call
+    /// [`refresh_sizes`][Self::refresh_sizes] afterwards.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn resize_encrypted_stack(
+        &'b self,
+        container: &Block,
+        new_size: u64,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let interactive = interactive.into();
+        for (path, interface, size) in self.cache.encrypted_resize_plan(container, new_size)? {
+            let options = options_builder().interactive(interactive).build();
+            self.proxy_with_timeout(path, timeout)
+                .method_call::<(), _, _, _>(interface, RESIZE, (size, options))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Concurrently lock every unlocked encrypted block, first tearing down the blocks stacked on
+    /// it, from the top: file systems are unmounted, nested containers are locked, and LVM
+    /// logical volumes are deactivated, so that the cleartext block is no longer in use.
+    ///
+    /// Failures don't stop the remaining blocks from being locked. The result of each is returned
+    /// alongside the encrypted block. Containers nested within another are locked along with it,
+    /// and aren't listed separately.
+    pub async fn lock_all(
+        &'b self,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Vec<(Block, Result<()>)> {
+        let interactive = interactive.into();
+        let containers = self.cache.outermost_unlocked();
+        let results = join_all(containers.iter().map(|container| async move {
+            let mut stack = self.cache.stacked_on(container);
+            stack.push(container.clone());
+            for block in &stack {
+                self.tear_down_block(block, interactive, timeout).await?;
+            }
+            Ok(())
+        }))
+        .await;
+        containers.into_iter().zip(results).collect()
+    }
+
+    /// Unmount the file system of a block, lock it if it's an unlocked container, and deactivate
+    /// it if it's a logical volume, so that the block it's stacked on is no longer in use.
+    async fn tear_down_block(
+        &self,
+        block: &Block,
+        interactive: Interactivity,
+        timeout: Duration,
+    ) -> Result<()> {
+        if !block.mount_points.is_empty() {
+            let options = UnmountOptions::default();
+            self.unmount(block.path.as_str(), &options, interactive, timeout)
+                .await?;
+        }
+        if matches!(block.encrypted, Some(ref e) if e.is_unlocked()) {
+            self.lock(block.path.as_str(), interactive, timeout).await?;
+        }
+        if let Some(ref lv) = block.logical_volume {
+            let options = options_builder().interactive(interactive).build();
+            self.proxy_with_timeout(lv.path.as_str(), timeout)
+                .method_call::<(), _, _, _>(LOGICAL_VOLUME, DEACTIVATE, (options,))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Eject the media of a drive, such as opening the tray of an optical drive. Any file
+    /// systems on it must be unmounted first. UDisks offers no options for ejecting, beyond
+    /// whether the user may be prompted for authentication.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn eject(
+        &'b self,
+        drive: &'b Drive,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        if !drive.ejectable {
+            return Err(UdisksError::NotEjectable(drive.path.clone()));
+        }
+        let proxy = self.proxy_with_timeout(drive, timeout);
+        let options = options_builder().interactive(interactive).build();
+        proxy
+            .method_call(drive::DEST, drive::EJECT, (options,))
+            .await
+            .map_err(Into::into)
+    }
+
+    /// The encryption type used by UDisks when formatting with encryption, such as `"luks1"` or
+    /// `"luks2"`. Daemons older than UDisks 2.8, which lack this setting, always use `"luks1"`.
+    pub async fn default_encryption_type(&self) -> Result<String> {
+        let result = self
+            .proxy(manager::PATH)
+            .get::<String>(manager::DEST, manager::DEFAULT_ENCRYPTION_TYPE)
+            .await;
+        match result {
+            Err(ref why) if manager::is_missing_property(why) => {
+                Ok(manager::LEGACY_ENCRYPTION_TYPE.to_owned())
+            }
+            result => result.map_err(Into::into),
+        }
+    }
+
+    /// The operations UDisks supports for each file system type it knows of, keyed by type, such
+    /// as `"ext4"`, to gray out unsupported actions such as shrinking XFS.
+    ///
+    /// The types are read from the manager's `SupportedFilesystems`, then each is queried with
+    /// `CanFormat`, `CanResize`, `CanCheck`, and `CanRepair`. Daemons older than UDisks 2.7,
+    /// which lack these methods, return an empty map.
+    pub async fn filesystem_capabilities(
+        &self,
+    ) -> Result<HashMap<String, FsCapabilities>, dbus::Error> {
+        match self.query_filesystem_capabilities().await {
+            Err(ref why)
+                if manager::is_missing_property(why) || manager::is_missing_method(why) =>
+            {
+                Ok(HashMap::new())
+            }
+            result => result,
+        }
+    }
+
+    async fn query_filesystem_capabilities(
+        &self,
+    ) -> Result<HashMap<String, FsCapabilities>, dbus::Error> {
+        let proxy = self.proxy(manager::PATH);
+        let types: Vec<String> = proxy
+            .get(manager::DEST, manager::SUPPORTED_FILESYSTEMS)
+            .await?;
+        let queries = types.into_iter().map(|fstype| {
+            let proxy = &proxy;
+            async move {
+                // Each method replies with a single struct: `(bs)`, or `(bts)` for `CanResize`.
+                let (create, resize, check, repair) = join!(
+                    proxy.method_call::<((bool, String),), _, _, _>(
+                        manager::DEST,
+                        manager::CAN_FORMAT,
+                        (&fstype,)
+                    ),
+                    proxy.method_call::<((bool, u64, String),), _, _, _>(
+                        manager::DEST,
+                        manager::CAN_RESIZE,
+                        (&fstype,)
+                    ),
+                    proxy.method_call::<((bool, String),), _, _, _>(
+                        manager::DEST,
+                        manager::CAN_CHECK,
+                        (&fstype,)
+                    ),
+                    proxy.method_call::<((bool, String),), _, _, _>(
+                        manager::DEST,
+                        manager::CAN_REPAIR,
+                        (&fstype,)
+                    ),
+                );
+                let ((resize, mode, _),) = resize?;
+                let resize = if resize { Some(mode) } else { None };
+                let value = FsCapabilities::new((create?.0).0, (check?.0).0, (repair?.0).0, resize);
+                Ok::<_, dbus::Error>((fstype, value))
+            }
+        });
+        Ok(try_join_all(queries).await?.into_iter().collect())
+    }
+
+    /// Format a block with an encrypted container, unlocked with `passphrase`, holding a new
+    /// file system of the type `fstype`. The encryption type is given by the
+    /// [`encrypt_type`][FormatOptions::encrypt_type] option, or the system default. You may pass
+    /// either a `&`[`Block`] or `&str` which is a path to a block, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// The container is left unlocked, and the path of its cleartext block is returned.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn format_encrypted(
+        &'b self,
+        block: impl Into<dbus::Path<'b>>,
+        fstype: &str,
+        passphrase: &str,
+        options: &FormatOptions,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<dbus::Path<'static>> {
+        let proxy = self.proxy_with_timeout(block, timeout);
+        let mut options = options.build(interactive.into());
+        options.insert(
+            format::ENCRYPT_PASSPHRASE,
+            Variant(Box::new(passphrase.to_owned())),
+        );
+        proxy
+            .method_call::<(), _, _, _>(format::DEST, format::FORMAT, (fstype, options))
+            .await?;
+        proxy
+            .get(encrypted::DEST, encrypted::CLEARTEXT_DEVICE)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Load all of the optional UDisks modules, such as `lvm2`, `bcache`, and `zram`, which
+    /// provide additional interfaces. Modules which are not installed are skipped. Follow with an
+    /// [`update`][Self::update] to fetch the new interfaces.
+    pub async fn enable_modules(&self) -> Result<()> {
+        self.proxy(manager::PATH)
+            .method_call(manager::DEST, manager::ENABLE_MODULES, (true,))
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Ask polkit whether this process may perform the action without authenticating, such as
+    /// when running as root or when a local polkit rule allows it. A UI may use this to disable
+    /// actions which the user can't perform, rather than attempting them and failing with
+    /// `NotAuthorized`.
+    ///
+    /// This doesn't prompt for authentication, so `false` is returned both when authentication
+    /// would be required and when the action is denied outright.
+    pub async fn check_authorization(&self, action: UdisksAction) -> Result<bool, dbus::Error> {
+        let proxy = nonblock::Proxy::new(polkit::BUS_NAME, polkit::PATH, self.timeout, &*self.conn);
+        let details: HashMap<&str, &str> = HashMap::new();
+        let ((authorized, _challenge, _details),): ((bool, bool, HashMap<String, String>),) = proxy
+            .method_call(
+                polkit::DEST,
+                polkit::CHECK_AUTHORIZATION,
+                (polkit::subject(), action.action_id(), details, 0u32, ""),
+            )
+            .await?;
+        Ok(authorized)
+    }
+
+    /// Cancel a running job. You may pass either a `&`[`Job`] or `&str` which is a path to a
+    /// job, starting with `/org/freedesktop/UDisks2/jobs/`. Jobs which are not `cancelable`
+    /// return an error from UDisks.
+    ///
+    /// This is the only way to stop an operation on the server: dropping the future of a method
+    /// call, such as [`format`][Self::format], merely stops waiting for the reply, and the job
+    /// carries on regardless. Find the job with [`jobs_of`][Self::jobs_of] after an
+    /// [`update`][Self::update], which is best done with the `no_block` option so that the call
+    /// returns while the job is still running.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn cancel_job(
+        &'b self,
+        job: impl Into<dbus::Path<'b>>,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let options = options_builder().interactive(interactive).build();
+        self.proxy_with_timeout(job, timeout)
+            .method_call(job::DEST, job::CANCEL, (options,))
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Concurrently delete every loop device which is backed by the given file, returning how
+    /// many were deleted. Loop devices are matched from the cache, so it should be up to date.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn loop_delete_by_backing_file(
+        &'b self,
+        file: &Path,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<usize> {
+        let interactive = interactive.into();
+        let loops = self.cache.loops_backed_by(file);
+        try_join_all(loops.iter().map(|path| async move {
+            let options = options_builder().interactive(interactive).build();
+            self.proxy_with_timeout(path.clone(), timeout)
+                .method_call::<(), _, _, _>(loop_device::DEST, loop_device::DELETE, (options,))
+                .await
+        }))
+        .await?;
+        Ok(loops.len())
+    }
+
+    /// Get the current power mode of a drive, without waking it up. You may pass either a
+    /// `&`[`Drive`] or `&str` which is a path to a drive, starting with
+    /// `/org/freedesktop/UDisks2/drives/`.
+    pub async fn power_state(&'b self, drive: impl Into<dbus::Path<'b>>) -> Result<PowerState> {
+        let options = options_builder().build();
+        let (state,): (u8,) = self
+            .proxy(drive)
+            .method_call(power::DEST, power::PM_GET_STATE, (options,))
+            .await?;
+        Ok(PowerState::from(state))
+    }
+
+    /// Wait until a drive has entered the `target` power mode, such as to confirm that a drive
+    /// has entered standby. The power mode is checked once per second, without waking the drive.
+    ///
+    /// Returns [`UdisksError::TimedOut`] if the drive has not reached `target` within `timeout`.
+    ///
+    /// The wait between checks is serviced by a thread of its own, so this works without relying
+    /// on the timer of any particular runtime.
+    pub async fn await_power_state(
+        &'b self,
+        drive: impl Into<dbus::Path<'b>>,
+        target: PowerState,
+        timeout: Duration,
+    ) -> Result<()> {
+        let drive = drive.into();
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.power_state(drive.clone()).await? == target {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(UdisksError::TimedOut(drive.to_string()));
+            }
+
+            Sleep::new(POWER_STATE_INTERVAL.min(deadline - now)).await;
+        }
+    }
+}
+
+impl<C> AsyncUDisks2<C>
+where
+    C: Deref<Target = nonblock::SyncConnection>,
+{
+    /// Wait until media is inserted into a removable drive, such as a disc into an optical drive,
+    /// then refresh the cache. Returns immediately if the drive already has media.
+    ///
+    /// This listens for the drive's `PropertiesChanged` signals rather than polling, so it
+    /// requires a [`SyncConnection`][nonblock::SyncConnection], such as the one created by
+    /// `dbus_tokio::connection::new_system_sync`.
+    ///
+    /// Returns [`UdisksError::TimedOut`] if no media was inserted within `timeout`. The match
+    /// rule is removed from the bus even if the future is dropped before completing.
+    pub async fn await_media<'a>(
+        &mut self,
+        drive: impl Into<dbus::Path<'a>>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let path = drive.into().into_static();
+        let rule = PropertiesPropertiesChanged::match_rule(None, Some(&path)).static_clone();
+        let (changes_match, mut changes) = self
+            .conn
+            .add_match(rule)
+            .await?
+            .stream::<PropertiesPropertiesChanged>();
+        let guard = MatchGuard {
+            conn: &*self.conn,
+            matches: vec![changes_match],
+        };
+
+        let inserted = async {
+            // Check after subscribing, so that media inserted in between isn't missed.
+            let proxy = self.proxy(path.clone());
+            if proxy.get(drive::DEST, drive::MEDIA_AVAILABLE).await? {
+                return Ok(());
+            }
+
+            while let Some((_, changed)) = changes.next().await {
+                let available = changed.changed_properties.get(drive::MEDIA_AVAILABLE);
+                if changed.interface_name == drive::DEST
+                    && matches!(available, Some(v) if get_bool(v))
+                {
+                    return Ok(());
+                }
+            }
+
+            Err(dbus::Error::new_failed("PropertiesChanged stream ended"))
+        };
+
+        let result = match select(Box::pin(inserted), Sleep::new(timeout)).await {
+            Either::Left((result, _)) => result.map_err(UdisksError::from),
+            Either::Right(_) => Err(UdisksError::TimedOut(path.to_string())),
+        };
+
+        drop(guard);
+        result?;
+        self.update().await.map_err(Into::into)
+    }
+
+    /// Safely remove a drive, such as a USB disk, and wait for UDisks to confirm that it's gone.
+    /// Every file system on the drive is unmounted, its unlocked encrypted containers are
+    /// locked, and the drive is powered off, after which UDisks removes its object. Once this
+    /// returns, the drive may be unplugged.
+    ///
+    /// Drives which can't be powered off, according to `can_power_off`, are ejected instead if
+    /// they are `ejectable`, and are otherwise left with their file systems unmounted. Either
+    /// way their object remains until the drive is physically unplugged, so this then waits for
+    /// the user to do so.
+    ///
+    /// Blocks stacked on those of the drive are torn down first, from the top: file systems on
+    /// cleartext blocks and LVM logical volumes are unmounted, nested containers are locked, and
+    /// logical volumes are deactivated, so that the containers on the drive aren't busy when
+    /// they are locked.
+    ///
+    /// This listens for the manager's `InterfacesRemoved` signal rather than polling, so it
+    /// requires a [`SyncConnection`][nonblock::SyncConnection]. `timeout` applies to each step
+    /// of the teardown, and then to waiting for the removal, which returns
+    /// [`UdisksError::TimedOut`] if the drive is still present by then. The cache is not
+    /// updated; call [`update`][Self::update] afterwards.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    pub async fn safe_remove_verified(
+        &self,
+        drive: &Drive,
+        interactive: impl Into<Interactivity>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let manager = dbus::Path::from(PATH);
+        let rule = ObjectManagerInterfacesRemoved::match_rule(None, Some(&manager)).static_clone();
+        let (removals_match, mut removals) = self
+            .conn
+            .add_match(rule)
+            .await?
+            .stream::<ObjectManagerInterfacesRemoved>();
+        let _guard = MatchGuard {
+            conn: &*self.conn,
+            matches: vec![removals_match],
+        };
+
+        // Subscribe before tearing down, so that a quick removal isn't missed.
+        self.tear_down_drive(drive, interactive.into(), timeout)
+            .await?;
+
+        let removed = async {
+            while let Some((_, removed)) = removals.next().await {
+                if removed.object.deref() == drive.path
+                    && removed.interfaces.iter().any(|i| i == drive::DEST)
+                {
+                    return Ok(());
+                }
+            }
+            Err(dbus::Error::new_failed("InterfacesRemoved stream ended"))
+        };
+
+        match select(Box::pin(removed), Sleep::new(timeout)).await {
+            Either::Left((result, _)) => result.map_err(UdisksError::from),
+            Either::Right(_) => Err(UdisksError::TimedOut(drive.path.clone())),
+        }
+    }
+
+    /// Unmount every file system of a drive and lock its encrypted containers, along with the
+    /// blocks stacked on them, then power it off, or eject it if it can't be powered off.
+    async fn tear_down_drive(
+        &self,
+        drive: &Drive,
+        interactive: Interactivity,
+        timeout: Duration,
+    ) -> Result<()> {
+        // Partitions come before the whole disk, and the blocks stacked on each before the
+        // block itself.
+        let mut blocks = Vec::new();
+        for block in self.blocks_of_drive(drive).into_iter().rev() {
+            if block.drive != drive.path {
+                continue;
+            }
+            for stacked in self.cache.stacked_on(&block) {
+                if !blocks.iter().any(|b: &Block| b.path == stacked.path) {
+                    blocks.push(stacked);
+                }
+            }
+            blocks.push(block);
+        }
+
+        for block in &blocks {
+            self.tear_down_block(block, interactive, timeout).await?;
+        }
+
+        if drive.can_power_off {
+            let options = options_builder().interactive(interactive).build();
+            self.proxy_with_timeout(drive, timeout)
+                .method_call::<(), _, _, _>(drive::DEST, drive::POWER_OFF, (options,))
+                .await?;
+        } else if drive.ejectable {
+            self.eject(drive, interactive, timeout).await?;
+        }
+        Ok(())
+    }
+
+    /// Watch the mount points of a block, yielding them each time the block is mounted or
+    /// unmounted. The stream ends once the block is removed, such as when its drive is unplugged.
+    ///
+    /// This listens for the block's `PropertiesChanged` signals, along with the manager's
+    /// `InterfacesRemoved`, rather than polling, so it requires a
+    /// [`SyncConnection`][nonblock::SyncConnection]. The cache is not updated. The match rules
+    /// are removed from the bus once the stream is dropped.
+    pub async fn watch_block_mount(&self, block: &Block) -> Result<impl Stream<Item = Vec<PathBuf>>>
+    where
+        C: Clone + Unpin,
+    {
+        let path = dbus::Path::from(block).into_static();
+        let manager = dbus::Path::from(PATH);
+
+        let mut guard = MatchGuard {
+            conn: self.conn.clone(),
+            matches: Vec::new(),
+        };
+        let rule = PropertiesPropertiesChanged::match_rule(None, Some(&path)).static_clone();
+        let (changes_match, changes) = self
+            .conn
+            .add_match(rule)
+            .await?
+            .stream::<PropertiesPropertiesChanged>();
+        guard.matches.push(changes_match);
+        let rule = ObjectManagerInterfacesRemoved::match_rule(None, Some(&manager)).static_clone();
+        let (removals_match, removals) = self
+            .conn
+            .add_match(rule)
+            .await?
+            .stream::<ObjectManagerInterfacesRemoved>();
+        guard.matches.push(removals_match);
+
+        let changes = changes.filter_map(|(_, changed)| {
+            let mount_points = if changed.interface_name == filesystem::DEST {
+                changed
+                    .changed_properties
+                    .get(filesystem::MOUNT_POINTS)
+                    .map(|value| {
+                        let paths = get_array_of_byte_arrays(value).unwrap_or_default();
+                        MountEvent::Changed(paths.into_iter().map(PathBuf::from).collect())
+                    })
+            } else {
+                None
+            };
+            future::ready(mount_points)
+        });
+        let removals = removals.filter_map(move |(_, removed)| {
+            let is_block =
+                removed.object == path && removed.interfaces.iter().any(|i| i == crate::BLOCK);
+            future::ready(if is_block {
+                Some(MountEvent::Removed)
+            } else {
+                None
+            })
+        });
+
+        let mount_points = stream::select(changes, removals)
+            .take_while(|event| future::ready(matches!(event, MountEvent::Changed(_))))
+            .filter_map(|event| {
+                future::ready(match event {
+                    MountEvent::Changed(mount_points) => Some(mount_points),
+                    MountEvent::Removed => None,
+                })
+            });
+
+        Ok(Guarded {
+            stream: mount_points,
+            _guard: guard,
+        })
+    }
+
+    /// Start unmounting the file system of a block, without waiting for UDisks to finish. You
+    /// may pass either a `&`[`Block`] or `&str` which is a path to a block, starting with
+    /// `/org/freedesktop/UDisks2/block_devices/`.
+    ///
+    /// UDisks has no `no_block` option for unmounting, and doesn't return a job, so the call is
+    /// instead sent without asking for a reply. Only failing to send it is an error: whether the
+    /// unmount succeeded, or failed because the file system is busy, is never reported. While it
+    /// runs, UDisks shows the unmount as a job with the operation `"filesystem-unmount"`, found
+    /// with [`jobs_of`][Self::jobs_of] after an [`update`][Self::update]. Once the block's
+    /// `mount_points` are empty, it has finished.
+    ///
+    /// If `interactive`, the user may be prompted for authentication by a polkit agent. See
+    /// [`Interactivity`] for the behavior without an agent.
+    ///
+    /// This requires a [`SyncConnection`][nonblock::SyncConnection], which sends messages
+    /// without awaiting a reply.
+    pub fn unmount_no_wait<'a>(
+        &'a self,
+        block: impl Into<dbus::Path<'a>>,
+        options: &UnmountOptions,
+        interactive: impl Into<Interactivity>,
+    ) -> Result<()> {
+        let mut message = Message::method_call(
+            &DEST.into(),
+            &block.into(),
+            &filesystem::DEST.into(),
+            &filesystem::UNMOUNT.into(),
+        )
+        .append1(options.build(interactive.into()));
+        message.set_no_reply(true);
+        self.conn
+            .send(message)
+            .map(|_| ())
+            .map_err(|()| dbus::Error::new_failed("failed to send Unmount").into())
+    }
+}
+
+/// Matches which keep receiving signals until dropped, when their rules are removed from the
+/// bus, so that a future or stream which subscribed to signals doesn't leave them behind when
+/// it's dropped or cancelled.
+struct MatchGuard<C>
+where
+    C: Deref<Target = nonblock::SyncConnection>,
+{
+    conn: C,
+    matches: Vec<nonblock::MsgMatch>,
+}
+
+impl<C> Drop for MatchGuard<C>
+where
+    C: Deref<Target = nonblock::SyncConnection>,
+{
+    fn drop(&mut self) {
+        // Drop can't await the reply of `remove_match`, so `RemoveMatch` is sent without one.
+        for msg_match in self.matches.drain(..) {
+            if let Some((rule, _)) = self.conn.stop_receive(msg_match.token()) {
+                let mut message = Message::method_call(
+                    &"org.freedesktop.DBus".into(),
+                    &"/org/freedesktop/DBus".into(),
+                    &"org.freedesktop.DBus".into(),
+                    &"RemoveMatch".into(),
+                )
+                .append1(rule.match_str());
+                message.set_no_reply(true);
+                let _ = self.conn.send(message);
+            }
+        }
+    }
+}
+
+/// A stream which owns the match rules it receives signals from.
+struct Guarded<C, S>
+where
+    C: Deref<Target = nonblock::SyncConnection>,
+{
+    stream: S,
+    _guard: MatchGuard<C>,
+}
+
+impl<C, S> Stream for Guarded<C, S>
+where
+    C: Deref<Target = nonblock::SyncConnection> + Unpin,
+    S: Stream + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        self.stream.poll_next_unpin(cx)
+    }
+}
+
+/// An event of [`AsyncUDisks2::watch_block_mount`].
+enum MountEvent {
+    Changed(Vec<PathBuf>),
+    Removed,
+}
+
+/// A future which completes once `duration` has passed, woken by a thread of its own. Dropping
+/// it ends the thread early, rather than leaving it to sleep out the duration.
+struct Sleep {
+    state: Arc<(Mutex<SleepState>, Condvar)>,
+}
+
+#[derive(Default)]
+struct SleepState {
+    done: bool,
+    cancelled: bool,
+    waker: Option<Waker>,
+}
+
+impl Sleep {
+    fn new(duration: Duration) -> Self {
+        let state = Arc::new((Mutex::new(SleepState::default()), Condvar::new()));
+        let thread_state = state.clone();
+        std::thread::spawn(move || {
+            let (lock, cancel) = &*thread_state;
+            let state = lock.lock().unwrap();
+            let (mut state, _) = cancel
+                .wait_timeout_while(state, duration, |state| !state.cancelled)
+                .unwrap();
+            if !state.cancelled {
+                state.done = true;
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        });
+        Sleep { state }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.0.lock().unwrap();
+        if state.done {
+            Poll::Ready(())
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        let (lock, cancel) = &*self.state;
+        lock.lock().unwrap().cancelled = true;
+        cancel.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleep_completes() {
+        let start = Instant::now();
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(Sleep::new(Duration::from_millis(50)));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn dropped_sleep_ends_thread() {
+        let sleep = Sleep::new(Duration::from_secs(60));
+        let state = sleep.state.clone();
+        drop(sleep);
+
+        // The thread holds the only other reference to the state until it ends.
+        let start = Instant::now();
+        while Arc::strong_count(&state) > 1 {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "thread still sleeping"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
 }
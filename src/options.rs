@@ -0,0 +1,160 @@
+//! Building the `a{sv}` options dictionaries taken by UDisks methods.
+//!
+//! This is useful when calling methods of UDisks which are not wrapped by this crate.
+//!
+//! ```
+//! use dbus_udisks2::options::{self, NO_USER_INTERACTION};
+//!
+//! let options = options::options_builder()
+//!     .interactive(false)
+//!     .insert("force", true)
+//!     .build();
+//! assert!(options.contains_key(NO_USER_INTERACTION));
+//! ```
+
+use dbus::arg::{RefArg, Variant};
+use std::collections::HashMap;
+
+/// A dictionary of options, as taken by most UDisks methods.
+pub type KeyVariant<K = String> = HashMap<K, Variant<Box<dyn RefArg>>>;
+
+/// Accepted by all methods which may require authorization. When `true`, UDisks fails the call
+/// instead of asking a polkit agent to prompt the user for authentication.
+pub const NO_USER_INTERACTION: &str = "auth.no_user_interaction";
+/// Accepted by the S.M.A.R.T. methods of `org.freedesktop.UDisks2.Drive.Ata`. When `true`, the
+/// drive isn't woken up if it's in a sleeping state.
+pub const NO_WAKEUP: &str = "nowakeup";
+
+/// Whether UDisks may ask a polkit agent to prompt the user for authentication, when a method
+/// requires authorization.
+///
+/// Without a polkit agent, such as in a headless session, an interactive call which requires
+/// authentication hangs until the method call times out instead of failing, so headless callers
+/// should use [`NonInteractive`][Interactivity::NonInteractive].
+///
+/// Methods accept either an `Interactivity` or a `bool`, where `true` is
+/// [`Interactive`][Interactivity::Interactive] and `false` is
+/// [`NonInteractive`][Interactivity::NonInteractive].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Interactivity {
+    /// Leave [`NO_USER_INTERACTION`] unset, deferring to the default of UDisks, which is to
+    /// allow interaction.
+    Default,
+    /// Set [`NO_USER_INTERACTION`] to `false`, allowing the user to be prompted.
+    Interactive,
+    /// Set [`NO_USER_INTERACTION`] to `true`, failing the call instead of prompting the user.
+    NonInteractive,
+}
+
+// Deriving the default of an enum requires Rust 1.62.
+#[allow(clippy::derivable_impls)]
+impl Default for Interactivity {
+    fn default() -> Self {
+        Interactivity::Default
+    }
+}
+
+impl Interactivity {
+    /// The value of [`NO_USER_INTERACTION`] to set, if any.
+    pub(crate) fn no_user_interaction(self) -> Option<bool> {
+        match self {
+            Interactivity::Default => None,
+            Interactivity::Interactive => Some(false),
+            Interactivity::NonInteractive => Some(true),
+        }
+    }
+}
+
+impl From<bool> for Interactivity {
+    fn from(interactive: bool) -> Self {
+        if interactive {
+            Interactivity::Interactive
+        } else {
+            Interactivity::NonInteractive
+        }
+    }
+}
+
+/// Whether a drive in a sleeping state may be woken up by a S.M.A.R.T. method, such as
+/// [`smart_update`][crate::UDisks2::smart_update].
+///
+/// This replaces the `allow_wakeup: bool` arguments of these methods, which were easily misread
+/// as the value of [`NO_WAKEUP`], their negation. Callers passing `true` should now pass
+/// [`Allow`][Wakeup::Allow], and those passing `false` should pass [`Prevent`][Wakeup::Prevent].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Wakeup {
+    /// Leave [`NO_WAKEUP`] unset, so that a sleeping drive is woken up, which is the default of
+    /// UDisks.
+    Allow,
+    /// Set [`NO_WAKEUP`] to `true`, so that a sleeping drive is left asleep. UDisks then fails
+    /// the call rather than waking the drive.
+    Prevent,
+}
+
+// Deriving the default of an enum requires Rust 1.62.
+#[allow(clippy::derivable_impls)]
+impl Default for Wakeup {
+    fn default() -> Self {
+        Wakeup::Allow
+    }
+}
+
+impl Wakeup {
+    /// Set [`NO_WAKEUP`] in the options if the drive should be left asleep.
+    pub(crate) fn apply(self, options: &mut KeyVariant<&str>) {
+        if self == Wakeup::Prevent {
+            options.insert(NO_WAKEUP, Variant(Box::new(true)));
+        }
+    }
+}
+
+/// Create an empty [`OptionsBuilder`].
+pub fn options_builder() -> OptionsBuilder {
+    OptionsBuilder::default()
+}
+
+/// Builds a [`KeyVariant`] of options.
+#[derive(Debug, Default)]
+pub struct OptionsBuilder(KeyVariant);
+
+impl OptionsBuilder {
+    /// Set an option. The key must exactly match the option name used by UDisks, and the value
+    /// must be of the type UDisks expects for it.
+    pub fn insert(mut self, key: impl Into<String>, value: impl RefArg + 'static) -> Self {
+        self.0.insert(key.into(), Variant(Box::new(value)));
+        self
+    }
+
+    /// Whether the user may be prompted for authentication. Sets [`NO_USER_INTERACTION`] unless
+    /// [`Interactivity::Default`] is given.
+    pub fn interactive(self, interactive: impl Into<Interactivity>) -> Self {
+        match interactive.into().no_user_interaction() {
+            Some(value) => self.insert(NO_USER_INTERACTION, value),
+            None => self,
+        }
+    }
+
+    /// Whether a sleeping drive may be woken up. Sets [`NO_WAKEUP`] unless
+    /// [`Wakeup::Allow`] is given.
+    pub fn wakeup(self, wakeup: Wakeup) -> Self {
+        match wakeup {
+            Wakeup::Allow => self,
+            Wakeup::Prevent => self.insert(NO_WAKEUP, true),
+        }
+    }
+
+    /// Whether a sleeping drive may be woken up. Sets [`NO_WAKEUP`] if not.
+    #[deprecated(note = "use `wakeup` with a `Wakeup`, which can't be mistaken for `NO_WAKEUP`")]
+    pub fn allow_wakeup(self, allow_wakeup: bool) -> Self {
+        self.wakeup(if allow_wakeup {
+            Wakeup::Allow
+        } else {
+            Wakeup::Prevent
+        })
+    }
+
+    /// The options set so far, to be passed as the `a{sv}` options argument of a UDisks method.
+    pub fn build(self) -> KeyVariant {
+        self.0
+    }
+}
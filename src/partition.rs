@@ -0,0 +1,124 @@
+//! Types related to partitions and partition tables.
+
+use crate::options::{Interactivity, NO_USER_INTERACTION};
+use crate::utils::*;
+use dbus::arg::Variant;
+
+pub(crate) const DEST: &str = "org.freedesktop.UDisks2.Partition";
+pub(crate) const TABLE_DEST: &str = "org.freedesktop.UDisks2.PartitionTable";
+pub(crate) const CREATE_PARTITION: &str = "CreatePartition";
+const PARTITION_TYPE: &str = "partition-type";
+
+/// The alignment used by [`PartitionOptions::aligned`], which is that of `parted`, `fdisk`, and
+/// most other partitioning tools. It's a multiple of the erase block and optimal I/O sizes of
+/// nearly every device.
+pub const DEFAULT_ALIGNMENT: u64 = 1024 * 1024;
+
+#[derive(Debug, Default)]
+/// Options for [`create_partition`][crate::UDisks2::create_partition]
+/// ([async version][crate::AsyncUDisks2::create_partition]).
+pub struct PartitionOptions {
+    /// The partition type: a GUID for a GPT, such as `"0fc63daf-8483-4772-8e79-3d69d8477de4"`,
+    /// or a hex byte for a MBR, such as `"0x83"`. Empty to let UDisks choose.
+    pub type_: String,
+    /// The name of the partition. Only supported by GPT, and must be empty for a MBR.
+    pub name: String,
+    /// For a MBR, whether to create a `"primary"`, `"extended"`, or `"logical"` partition.
+    /// UDisks chooses if unset.
+    pub partition_type: Option<String>,
+    /// Align the partition to [`DEFAULT_ALIGNMENT`] before creating it. See [`align_region`]
+    /// for how the offset and size are adjusted. With a size of zero, to fill the free space,
+    /// only the offset is aligned.
+    pub aligned: bool,
+    /// Additional options passed to UDisks as-is, for options not modeled by this struct. Keys
+    /// must exactly match the option names of
+    /// `org.freedesktop.UDisks2.PartitionTable.CreatePartition`, and take precedence over the
+    /// fields above.
+    pub extra: KeyVariant,
+}
+
+impl PartitionOptions {
+    pub(crate) fn build(&self, interactive: Interactivity) -> KeyVariant<&str> {
+        let mut options = KeyVariant::<&str>::new();
+        if let Some(ref partition_type) = self.partition_type {
+            options.insert(PARTITION_TYPE, Variant(Box::new(partition_type.clone())));
+        }
+        if let Some(value) = interactive.no_user_interaction() {
+            options.insert(NO_USER_INTERACTION, Variant(Box::new(value)));
+        }
+        merge_extra(&mut options, &self.extra);
+        options
+    }
+
+    /// The offset and size to request, aligned if [`aligned`][Self::aligned] is set. A size of
+    /// zero, which has UDisks fill the free space, is kept, with only the offset aligned. `None`
+    /// if no aligned space remains of a nonzero size.
+    pub(crate) fn region(&self, offset: u64, size: u64) -> Option<(u64, u64)> {
+        if !self.aligned {
+            return Some((offset, size));
+        }
+        if size == 0 {
+            return Some((align_region(offset, 0, DEFAULT_ALIGNMENT).0, 0));
+        }
+        match align_region(offset, size, DEFAULT_ALIGNMENT) {
+            (_, 0) => None,
+            region => Some(region),
+        }
+    }
+}
+
+/// Align a region so that it lies within the given one, with both of its ends on a multiple of
+/// `alignment`: the offset is rounded up, and the end, at `offset + size`, is rounded down.
+/// Returns the new offset and size, the size being zero if no aligned region fits.
+///
+/// An `alignment` of zero leaves the region as it is.
+///
+/// ```
+/// use dbus_udisks2::partition::{align_region, DEFAULT_ALIGNMENT};
+///
+/// const MIB: u64 = DEFAULT_ALIGNMENT;
+/// assert_eq!(align_region(17 * 1024, 100 * MIB, MIB), (MIB, 99 * MIB));
+/// assert_eq!(align_region(MIB, 100 * MIB, MIB), (MIB, 100 * MIB));
+/// ```
+pub fn align_region(offset: u64, size: u64, alignment: u64) -> (u64, u64) {
+    if alignment == 0 {
+        return (offset, size);
+    }
+    let start = match offset % alignment {
+        0 => offset,
+        rem => offset.saturating_add(alignment - rem),
+    };
+    let end = offset.saturating_add(size);
+    let end = end - end % alignment;
+    (start, end.saturating_sub(start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = DEFAULT_ALIGNMENT;
+
+    #[test]
+    fn aligns_region() {
+        let options = PartitionOptions {
+            aligned: true,
+            ..Default::default()
+        };
+        assert_eq!(options.region(17 * 1024, 100 * MIB), Some((MIB, 99 * MIB)));
+        assert_eq!(options.region(17 * 1024, MIB / 2), None);
+    }
+
+    #[test]
+    fn aligns_offset_of_fill() {
+        let options = PartitionOptions {
+            aligned: true,
+            ..Default::default()
+        };
+        assert_eq!(options.region(17 * 1024, 0), Some((MIB, 0)));
+        assert_eq!(options.region(MIB, 0), Some((MIB, 0)));
+
+        let unaligned = PartitionOptions::default();
+        assert_eq!(unaligned.region(17 * 1024, 0), Some((17 * 1024, 0)));
+    }
+}
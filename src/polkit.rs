@@ -0,0 +1,101 @@
+//! Querying polkit for whether UDisks would authorize an operation.
+
+use dbus::arg::{RefArg, Variant};
+
+use crate::utils::KeyVariant;
+
+pub(crate) const BUS_NAME: &str = "org.freedesktop.PolicyKit1";
+pub(crate) const PATH: &str = "/org/freedesktop/PolicyKit1/Authority";
+pub(crate) const DEST: &str = "org.freedesktop.PolicyKit1.Authority";
+pub(crate) const CHECK_AUTHORIZATION: &str = "CheckAuthorization";
+
+/// The polkit actions of UDisks, for
+/// [`UDisks2::check_authorization`][crate::UDisks2::check_authorization].
+///
+/// UDisks checks the `System` variant of an action instead for devices with `hint_system` set,
+/// which by default requires administrator authentication. See
+/// [`UDisks2::mount_requires_auth`][crate::UDisks2::mount_requires_auth].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum UdisksAction {
+    /// `org.freedesktop.udisks2.filesystem-mount`: mounting a file system.
+    Mount,
+    /// `org.freedesktop.udisks2.filesystem-mount-system`: mounting a file system of a system
+    /// device.
+    MountSystem,
+    /// `org.freedesktop.udisks2.filesystem-unmount-others`: unmounting a file system mounted by
+    /// another user.
+    UnmountOthers,
+    /// `org.freedesktop.udisks2.encrypted-unlock`: unlocking an encrypted device.
+    Unlock,
+    /// `org.freedesktop.udisks2.encrypted-unlock-system`: unlocking an encrypted system device.
+    UnlockSystem,
+    /// `org.freedesktop.udisks2.encrypted-change-passphrase`: changing the passphrase of an
+    /// encrypted device.
+    ChangePassphrase,
+    /// `org.freedesktop.udisks2.loop-setup`: setting up a loop device.
+    LoopSetup,
+    /// `org.freedesktop.udisks2.modify-device`: formatting, partitioning, resizing, and other
+    /// changes to a device.
+    ModifyDevice,
+    /// `org.freedesktop.udisks2.modify-device-system`: formatting, partitioning, resizing, and
+    /// other changes to a system device.
+    ModifyDeviceSystem,
+    /// `org.freedesktop.udisks2.eject-media`: ejecting media.
+    Eject,
+    /// `org.freedesktop.udisks2.eject-media-system`: ejecting media from a system device.
+    EjectSystem,
+    /// `org.freedesktop.udisks2.power-off-drive`: powering off a drive.
+    PowerOff,
+    /// `org.freedesktop.udisks2.power-off-drive-system`: powering off a system drive.
+    PowerOffSystem,
+    /// `org.freedesktop.udisks2.modify-drive-settings`: changing the settings of a drive, such as
+    /// its standby timeout.
+    ModifyDriveSettings,
+    /// `org.freedesktop.udisks2.ata-smart-selftest`: starting or aborting a SMART self-test.
+    SmartSelftest,
+    /// `org.freedesktop.udisks2.cancel-job`: cancelling a job started by the same user.
+    CancelJob,
+    /// `org.freedesktop.udisks2.cancel-job-other-user`: cancelling a job started by another user.
+    CancelJobOtherUser,
+    /// `org.freedesktop.udisks2.manage-swapspace`: starting or stopping swap.
+    ManageSwapspace,
+}
+
+impl UdisksAction {
+    /// The polkit action id, such as `"org.freedesktop.udisks2.filesystem-mount"`.
+    pub fn action_id(self) -> &'static str {
+        match self {
+            UdisksAction::Mount => "org.freedesktop.udisks2.filesystem-mount",
+            UdisksAction::MountSystem => "org.freedesktop.udisks2.filesystem-mount-system",
+            UdisksAction::UnmountOthers => "org.freedesktop.udisks2.filesystem-unmount-others",
+            UdisksAction::Unlock => "org.freedesktop.udisks2.encrypted-unlock",
+            UdisksAction::UnlockSystem => "org.freedesktop.udisks2.encrypted-unlock-system",
+            UdisksAction::ChangePassphrase => "org.freedesktop.udisks2.encrypted-change-passphrase",
+            UdisksAction::LoopSetup => "org.freedesktop.udisks2.loop-setup",
+            UdisksAction::ModifyDevice => "org.freedesktop.udisks2.modify-device",
+            UdisksAction::ModifyDeviceSystem => "org.freedesktop.udisks2.modify-device-system",
+            UdisksAction::Eject => "org.freedesktop.udisks2.eject-media",
+            UdisksAction::EjectSystem => "org.freedesktop.udisks2.eject-media-system",
+            UdisksAction::PowerOff => "org.freedesktop.udisks2.power-off-drive",
+            UdisksAction::PowerOffSystem => "org.freedesktop.udisks2.power-off-drive-system",
+            UdisksAction::ModifyDriveSettings => "org.freedesktop.udisks2.modify-drive-settings",
+            UdisksAction::SmartSelftest => "org.freedesktop.udisks2.ata-smart-selftest",
+            UdisksAction::CancelJob => "org.freedesktop.udisks2.cancel-job",
+            UdisksAction::CancelJobOtherUser => "org.freedesktop.udisks2.cancel-job-other-user",
+            UdisksAction::ManageSwapspace => "org.freedesktop.udisks2.manage-swapspace",
+        }
+    }
+}
+
+/// The subject of an authorization check: this process. A start time of zero has polkit look it
+/// up from the pid.
+pub(crate) fn subject() -> (&'static str, KeyVariant<&'static str>) {
+    let mut details: KeyVariant<&'static str> = KeyVariant::default();
+    details.insert(
+        "pid",
+        Variant(Box::new(std::process::id()) as Box<dyn RefArg>),
+    );
+    details.insert("start-time", Variant(Box::new(0u64) as Box<dyn RefArg>));
+    ("unix-process", details)
+}
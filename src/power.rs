@@ -0,0 +1,30 @@
+//! Types related to the power management of ATA drives.
+
+pub(crate) const DEST: &str = "org.freedesktop.UDisks2.Drive.Ata";
+pub(crate) const PM_GET_STATE: &str = "PmGetState";
+
+/// The power mode of an ATA drive, as reported by the `CHECK POWER MODE` command.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+#[non_exhaustive]
+pub enum PowerState {
+    /// The drive is in standby mode, with its platters spun down.
+    Standby,
+    /// The drive is in one of its idle modes.
+    Idle,
+    /// The drive is active or idle.
+    Active,
+    /// The drive reported a mode that is not known, such as a NV cache power mode.
+    Unknown(u8),
+}
+
+impl From<u8> for PowerState {
+    /// Interpret the count register returned by `CHECK POWER MODE`, as per ACS-3.
+    fn from(state: u8) -> Self {
+        match state {
+            0x00 | 0x01 => PowerState::Standby,
+            0x80..=0x83 => PowerState::Idle,
+            0xff => PowerState::Active,
+            other => PowerState::Unknown(other),
+        }
+    }
+}
@@ -20,13 +20,21 @@ pub(crate) const FAILING_ATTRS_COUNT: &str = "SmartNumAttributesFailing";
 pub(crate) const PAST_FAILING_ATTRS_COUNT: &str = "SmartNumAttributesFailedInThePast";
 pub(crate) const BAD_SECTORS: &str = "SmartNumBadSectors";
 pub(crate) const STATUS: &str = "SmartSelftestStatus";
+pub(crate) const PERCENT_REMAINING: &str = "SmartSelftestPercentRemaining";
+/// The sector size assumed when converting LBA counts to bytes.
+const SECTOR_SIZE: u64 = 512;
+/// The size of a megabyte, as the unit of [`PrettyUnit::Megabytes`].
+const MEGABYTE: u64 = 1000 * 1000;
+const TOTAL_LBAS_WRITTEN: u8 = 241;
+const TOTAL_LBAS_READ: u8 = 242;
 pub(crate) type RawSmartAttribute = (u8, String, u16, i32, i32, i32, i64, i32, KeyVariant);
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
 #[non_exhaustive]
 /// The status of a S.M.A.R.T. test.
 pub enum SmartStatus {
-    /// Last self-test was a success (or never ran).
+    /// Last self-test was a success, or no self-test has ever run. See
+    /// [`SmartData::selftest_ever_run`].
     Success,
     /// Last self-test was aborted.
     Aborted,
@@ -84,6 +92,30 @@ pub enum SmartValue {
     Enabled(SmartData),
 }
 
+impl SmartValue {
+    /// Summarize the health of the drive. See [`DriveHealth`] for how each level is decided.
+    pub fn health(&self) -> DriveHealth {
+        match self {
+            SmartValue::Enabled(data) => data.health(),
+            _ => DriveHealth::Unknown,
+        }
+    }
+}
+
+/// The overall health of a drive, ordered from the most to the least severe.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum DriveHealth {
+    /// The drive reports that it is about to fail, or an attribute is at or below its threshold.
+    Failing,
+    /// An attribute was below its threshold in the past, sectors have been reallocated or are
+    /// pending, or the most recent self-test failed.
+    Warning,
+    /// S.M.A.R.T. is unsupported, disabled, not yet read, or could not be read.
+    Unknown,
+    /// None of the above.
+    Healthy,
+}
+
 #[derive(Clone, Debug)]
 /// The S.M.A.R.T. data of a drive.
 pub struct SmartData {
@@ -106,6 +138,155 @@ pub struct SmartData {
     pub bad_sectors: i64,
     /// The status of the last self-test.
     pub status: SmartStatus,
+    /// The percent remaining of the self-test in progress, or `None` if no self-test is running.
+    pub selftest_percent_remaining: Option<i32>,
+}
+
+impl SmartData {
+    /// The status of the most recent self-test.
+    ///
+    /// UDisks only exposes the outcome of the most recent self-test. Unlike `smartctl`, it offers
+    /// no access to the drive's SMART error log or self-test log, and no way of clearing them.
+    pub fn last_selftest_status(&self) -> SmartStatus {
+        self.status
+    }
+
+    /// The attributes worth displaying, leaving out unknown vendor-specific attributes. All of
+    /// them remain in [`attributes`][Self::attributes].
+    ///
+    /// An attribute is kept if its name is known, or if a pretty value could be interpreted for
+    /// it. UDisks, through libatasmart, names attributes it doesn't know as `attribute-<id>`,
+    /// and reports an unknown unit for their pretty values, which leaves `pretty` as `None`.
+    pub fn standard_attributes(&self) -> impl Iterator<Item = &SmartAttribute> {
+        self.attributes
+            .iter()
+            .filter(|attr| !attr.name.starts_with("attribute-") || attr.pretty.is_some())
+    }
+
+    /// Summarize the health of the drive. See [`DriveHealth`] for how each level is decided.
+    pub fn health(&self) -> DriveHealth {
+        let attribute_failing = self
+            .attributes
+            .iter()
+            .any(|attr| attr.assessment() == SmartAssessment::Failing);
+        if self.failing || self.failing_attrs_count > 0 || attribute_failing {
+            DriveHealth::Failing
+        } else if self.past_failing_attrs_count > 0
+            || self.bad_sectors > 0
+            || self.selftest_passed() == Some(false)
+        {
+            DriveHealth::Warning
+        } else {
+            DriveHealth::Healthy
+        }
+    }
+
+    /// A one-line summary of the health of the drive for showing to the user, following the
+    /// wording of GNOME Disks. The first which applies is returned:
+    ///
+    /// - `"DISK IS LIKELY TO FAIL SOON"`: the drive reports that it is `failing`
+    /// - `"SELF-TEST FAILED"`: the most recent self-test failed
+    /// - `"Disk is OK, but attributes are failing"`: `failing_attrs_count` is above zero
+    /// - `"Disk is OK, but attributes have failed in the past"`: `past_failing_attrs_count` is
+    ///   above zero
+    /// - `"Disk is OK, but has bad sectors"`: `bad_sectors` is above zero
+    /// - `"Disk is OK"`
+    ///
+    /// The strings are in English and will not change, so they may be used as keys for
+    /// translation. Use [`health`][Self::health] to decide on icons or colors instead.
+    pub fn overall_assessment_text(&self) -> &'static str {
+        if self.failing {
+            "DISK IS LIKELY TO FAIL SOON"
+        } else if self.selftest_passed() == Some(false) {
+            "SELF-TEST FAILED"
+        } else if self.failing_attrs_count > 0 {
+            "Disk is OK, but attributes are failing"
+        } else if self.past_failing_attrs_count > 0 {
+            "Disk is OK, but attributes have failed in the past"
+        } else if self.bad_sectors > 0 {
+            "Disk is OK, but has bad sectors"
+        } else {
+            "Disk is OK"
+        }
+    }
+
+    /// Find an attribute by its identifier.
+    pub fn attribute(&self, id: u8) -> Option<&SmartAttribute> {
+        self.attributes.iter().find(|attr| attr.id == id)
+    }
+
+    /// The total bytes written by the host, from attribute 241 (Total LBAs Written). A
+    /// headline figure for the endurance of SSDs.
+    ///
+    /// libatasmart reports this attribute in megabytes, taking its raw value to count units of
+    /// 32 MiB, as most SSDs do. A pretty value in sectors is converted assuming 512 byte sectors.
+    /// Some vendors count in other units, which can't be told from the attribute itself. `None`
+    /// if the attribute is missing, or its value is in neither unit.
+    pub fn total_bytes_written(&self) -> Option<u64> {
+        self.attribute_bytes(TOTAL_LBAS_WRITTEN)
+    }
+
+    /// The total bytes read by the host, from attribute 242 (Total LBAs Read). See
+    /// [`total_bytes_written`][Self::total_bytes_written] for how this is converted.
+    pub fn total_bytes_read(&self) -> Option<u64> {
+        self.attribute_bytes(TOTAL_LBAS_READ)
+    }
+
+    fn attribute_bytes(&self, id: u8) -> Option<u64> {
+        let pretty = self.attribute(id)?.pretty?;
+        let value = u64::try_from(pretty.value).ok()?;
+        match pretty.unit {
+            PrettyUnit::Megabytes => value.checked_mul(MEGABYTE),
+            PrettyUnit::Sectors => value.checked_mul(SECTOR_SIZE),
+            _ => None,
+        }
+    }
+
+    /// Whether a self-test is currently running. UDisks exposes no count or history of past
+    /// self-tests.
+    pub fn is_selftest_running(&self) -> bool {
+        self.status == SmartStatus::InProgress
+    }
+
+    /// Whether a self-test has ever run on the drive, or `None` if this can't be told.
+    ///
+    /// UDisks reports `"success"` both when the last self-test passed and when none has ever run,
+    /// as it passes on the self-test execution status of the drive's SMART data, for which ATA
+    /// defines a single value meaning "completed without error, or no self-test has ever been
+    /// run". UDisks does not expose the self-test log which would tell them apart, and
+    /// `selftest_percent_remaining` is `None` whenever no self-test is running, either way.
+    ///
+    /// So this is `Some(true)` for any known status other than [`SmartStatus::Success`], as the
+    /// drive has then run or is running a self-test, and `None` otherwise. It is never
+    /// `Some(false)`. A UI nudging the user to run a first self-test should treat `None` as
+    /// "maybe never".
+    pub fn selftest_ever_run(&self) -> Option<bool> {
+        match self.status {
+            SmartStatus::Success | SmartStatus::Unknown => None,
+            _ => Some(true),
+        }
+    }
+
+    /// Whether the most recent self-test passed.
+    ///
+    /// `None` if it is still running, was aborted or interrupted before completing, or its status
+    /// is unknown. A self-test which failed with an error or did not complete due to a fatal
+    /// error is `Some(false)`.
+    pub fn selftest_passed(&self) -> Option<bool> {
+        match self.status {
+            SmartStatus::Success => Some(true),
+            SmartStatus::Fatal
+            | SmartStatus::UnknownError
+            | SmartStatus::ElectricalError
+            | SmartStatus::ServoError
+            | SmartStatus::ReadError
+            | SmartStatus::HandlingError => Some(false),
+            SmartStatus::Aborted
+            | SmartStatus::Interrupted
+            | SmartStatus::InProgress
+            | SmartStatus::Unknown => None,
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, TryFromPrimitive, Copy, Clone, Hash)]
@@ -116,6 +297,12 @@ pub enum PrettyUnit {
     Milliseconds,
     Sectors,
     Millikelvin,
+    /// A percentage, of an attribute which is usually low, such as the available reserved space.
+    SmallPercent,
+    /// A percentage.
+    Percent,
+    /// Megabytes, of 1000 * 1000 bytes.
+    Megabytes,
 }
 
 #[derive(Eq, PartialEq, Copy, Clone, Hash)]
@@ -143,6 +330,8 @@ impl fmt::Display for PrettyValue {
             PrettyUnit::Millikelvin => {
                 write!(f, "{:.1} degrees C", self.value as f32 / 1000. - 273.15)
             }
+            PrettyUnit::SmallPercent | PrettyUnit::Percent => write!(f, "{}%", self.value),
+            PrettyUnit::Megabytes => write!(f, "{} MB", self.value),
         }
     }
 }
@@ -161,7 +350,8 @@ pub struct SmartAttribute {
     pub id: u8,
     /// The identifier as a string.
     pub name: String,
-    /// 16-bit attribute flags (bit 0 is prefail/oldage, bit 1 is online/offline).
+    /// 16-bit attribute flags (bit 0 is prefail/oldage, bit 1 is online/offline, bits 2 through 5
+    /// are performance, error rate, event count and self-preserving).
     pub flags: u16,
     /// The current value or -1 if unknown.
     pub normalized: i32,
@@ -205,16 +395,23 @@ impl SmartAttribute {
     pub fn online(&self) -> bool {
         self.flags & 0x02 > 0
     }
-    /// `true`: speed/performance
+    /// Bit 2 (performance bit) - If set, the attribute reflects the speed or performance of the
+    /// device, such as spin-up time or seek time, rather than its reliability.
     pub fn performance(&self) -> bool {
         self.flags & 0x04 > 0
     }
+    /// Bit 3 (error rate bit) - If set, the attribute tracks a rate of errors, such as the raw
+    /// read error rate or the seek error rate.
     pub fn error_rate(&self) -> bool {
         self.flags & 0x08 > 0
     }
+    /// Bit 4 (event count bit) - If set, the raw value of the attribute is a count of events,
+    /// such as power cycles or reallocated sectors.
     pub fn event_count(&self) -> bool {
         self.flags & 0x10 > 0
     }
+    /// Bit 5 (self-preserving bit) - If set, the attribute is collected and saved by the device
+    /// even when the host has disabled automatic attribute saving.
     pub fn self_preserving(&self) -> bool {
         self.flags & 0x20 > 0
     }
@@ -253,3 +450,58 @@ impl From<RawSmartAttribute> for SmartAttribute {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smart_data(attributes: Vec<RawSmartAttribute>) -> SmartData {
+        SmartData {
+            attributes: attributes.into_iter().map(SmartAttribute::from).collect(),
+            updated: 0,
+            failing: false,
+            time_powered_on: 0,
+            temperature: 0.0,
+            failing_attrs_count: 0,
+            past_failing_attrs_count: 0,
+            bad_sectors: 0,
+            status: SmartStatus::Success,
+            selftest_percent_remaining: None,
+        }
+    }
+
+    #[test]
+    fn total_bytes_in_megabytes() {
+        // As reported by libatasmart for a raw value of 300000 units of 32 MiB.
+        let data = smart_data(vec![
+            (
+                241,
+                "total-lbas-written".into(),
+                0x32,
+                99,
+                99,
+                0,
+                10_066_329,
+                7,
+                KeyVariant::new(),
+            ),
+            (
+                242,
+                "total-lbas-read".into(),
+                0x32,
+                99,
+                99,
+                0,
+                2_048,
+                3,
+                KeyVariant::new(),
+            ),
+        ]);
+
+        let written = data.attribute(241).unwrap().pretty.unwrap();
+        assert_eq!(written.unit, PrettyUnit::Megabytes);
+        assert_eq!(written.to_string(), "10066329 MB");
+        assert_eq!(data.total_bytes_written(), Some(10_066_329_000_000));
+        assert_eq!(data.total_bytes_read(), Some(2_048 * 512));
+    }
+}
@@ -0,0 +1,79 @@
+//! Device specifications as written in the first field of `/etc/fstab` or `/etc/crypttab`.
+
+use std::path::PathBuf;
+
+use crate::Block;
+
+/// A reference to a block, in any of the forms accepted by `/etc/fstab`. Resolved to a block by
+/// [`UDisks2::resolve_device`][crate::UDisks2::resolve_device].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeviceSpec {
+    /// A device node or symlink, such as `/dev/sda1` or `/dev/disk/by-id/...`.
+    Path(PathBuf),
+    /// The UUID of the file system or other content of the block, such as a LUKS container.
+    Uuid(String),
+    /// The label of the file system.
+    Label(String),
+    /// The UUID of the partition, from the partition table rather than its content.
+    PartUuid(String),
+    /// The name of a GPT partition.
+    PartLabel(String),
+}
+
+impl DeviceSpec {
+    /// Parse a device specification, following the syntax of util-linux for `/etc/fstab`. The
+    /// supported prefixes are:
+    ///
+    /// - `UUID=`: [`DeviceSpec::Uuid`]
+    /// - `LABEL=`: [`DeviceSpec::Label`]
+    /// - `PARTUUID=`: [`DeviceSpec::PartUuid`]
+    /// - `PARTLABEL=`: [`DeviceSpec::PartLabel`]
+    ///
+    /// The value may be enclosed in double quotes. Anything else, such as `/dev/sda1` or a bare
+    /// path, is taken to be a [`DeviceSpec::Path`].
+    ///
+    /// ```
+    /// use dbus_udisks2::DeviceSpec;
+    ///
+    /// assert_eq!(DeviceSpec::parse("UUID=\"1234-ABCD\""), DeviceSpec::Uuid("1234-ABCD".into()));
+    /// assert_eq!(DeviceSpec::parse("LABEL=home"), DeviceSpec::Label("home".into()));
+    /// assert_eq!(DeviceSpec::parse("/dev/sda1"), DeviceSpec::Path("/dev/sda1".into()));
+    /// ```
+    pub fn parse(spec: &str) -> DeviceSpec {
+        let spec = spec.trim();
+        let (tag, value) = match spec.split_once('=') {
+            Some((tag, value)) => (tag, value),
+            None => return DeviceSpec::Path(PathBuf::from(spec)),
+        };
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value)
+            .to_owned();
+        match tag {
+            "UUID" => DeviceSpec::Uuid(value),
+            "LABEL" => DeviceSpec::Label(value),
+            "PARTUUID" => DeviceSpec::PartUuid(value),
+            "PARTLABEL" => DeviceSpec::PartLabel(value),
+            _ => DeviceSpec::Path(PathBuf::from(spec)),
+        }
+    }
+
+    /// Whether the block is the one referred to. UUIDs are compared case-insensitively, as
+    /// `blkid` reports them in lowercase while `/etc/fstab` may not.
+    pub fn matches(&self, block: &Block) -> bool {
+        match self {
+            DeviceSpec::Path(path) => block.matches_device(path),
+            DeviceSpec::Uuid(uuid) => {
+                matches!(&block.id_uuid, Some(id) if id.eq_ignore_ascii_case(uuid))
+            }
+            DeviceSpec::Label(label) => block.id_label.as_ref() == Some(label),
+            DeviceSpec::PartUuid(uuid) => {
+                matches!(&block.partition, Some(p) if p.uuid.eq_ignore_ascii_case(uuid))
+            }
+            DeviceSpec::PartLabel(label) => {
+                matches!(&block.partition, Some(p) if &p.name == label)
+            }
+        }
+    }
+}
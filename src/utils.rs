@@ -1,7 +1,8 @@
+pub use crate::options::KeyVariant;
 use dbus::arg::{RefArg, Variant};
 use std::collections::HashMap;
 
-pub type KeyVariant<K = String> = HashMap<K, Variant<Box<dyn RefArg>>>;
+/// The interfaces of a D-Bus object, mapped to their properties.
 pub type DbusObjects = HashMap<String, KeyVariant>;
 
 pub fn get_string(arg: &Variant<Box<dyn RefArg>>) -> Option<String> {
@@ -18,6 +19,14 @@ pub fn get_u64(arg: &Variant<Box<dyn RefArg>>) -> u64 {
     arg.0.as_u64().unwrap_or(0)
 }
 
+pub fn get_i64(arg: &Variant<Box<dyn RefArg>>) -> i64 {
+    arg.0.as_i64().unwrap_or(0)
+}
+
+pub fn get_f64(arg: &Variant<Box<dyn RefArg>>) -> f64 {
+    arg.0.as_f64().unwrap_or(0.0)
+}
+
 pub fn get_bool(arg: &Variant<Box<dyn RefArg>>) -> bool {
     arg.0.as_u64().unwrap_or(0) != 0
 }
@@ -53,13 +62,19 @@ pub fn atostr<'a>(array: Option<Box<dyn Iterator<Item = &'a dyn RefArg> + 'a>>)
     })
 }
 
+/// The inverse of `atostr`: a NUL-terminated byte array.
+pub fn strtoa(string: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(string.len() + 1);
+    bytes.extend_from_slice(string.as_bytes());
+    bytes.push(0);
+    bytes
+}
+
+/// Read a variant containing a byte array as a string.
 pub fn vva(value: &dyn RefArg) -> Option<String> {
-    let viter = value.as_iter().and_then(|mut i| {
-        i.next().and_then(|i| {
-            i.as_iter()
-                .and_then(|mut i| i.next().and_then(|i| i.as_iter()))
-        })
-    });
+    let viter = value
+        .as_iter()
+        .and_then(|mut i| i.next().and_then(|i| i.as_iter()));
 
     atostr(viter)
 }
@@ -82,3 +97,10 @@ pub(crate) trait ParseFrom {
     where
         Self: Sized;
 }
+
+/// Insert copies of the `extra` options into `options`, replacing existing keys.
+pub fn merge_extra<'a>(options: &mut KeyVariant<&'a str>, extra: &'a KeyVariant) {
+    for (key, value) in extra {
+        options.insert(key.as_str(), Variant(value.0.box_clone()));
+    }
+}
@@ -0,0 +1,648 @@
+//! Runs the method wrappers against a mock UDisks2 service, which serves canned objects on the
+//! session bus under the name `org.freedesktop.UDisks2`.
+//!
+//! This requires the `test-integration` feature and a session bus, such as one started by
+//! `dbus-run-session -- cargo test --features test-integration`.
+
+#![cfg(feature = "test-integration")]
+
+use dbus::arg::{PropMap, RefArg, Variant};
+use dbus::blocking::stdintf::org_freedesktop_dbus::ObjectManagerInterfacesRemoved;
+use dbus::blocking::Connection;
+use dbus::channel::{MatchingReceiver, Sender};
+use dbus::message::{MatchRule, SignalArgs};
+use dbus::strings::ErrorName;
+use dbus::Message;
+use dbus_udisks2::filesystem::{MountOptions, UnmountOptions};
+use dbus_udisks2::format::FormatOptions;
+use dbus_udisks2::{UDisks2, UDisks2Builder, UdisksError};
+use std::collections::{BTreeSet, HashMap};
+use std::ffi::CString;
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const DRIVE: &str = "/org/freedesktop/UDisks2/drives/Mock_Disk_1234";
+const DISK: &str = "/org/freedesktop/UDisks2/block_devices/sda";
+const PARTITION: &str = "/org/freedesktop/UDisks2/block_devices/sda1";
+const MALFORMED: &str = "/org/freedesktop/UDisks2/block_devices/sdb";
+const MAPPER: &str = "/org/freedesktop/UDisks2/block_devices/dm_2d0";
+const MOUNT_PATH: &str = "/run/media/mock/data";
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a slow `GetManagedObjects` takes to reply. See [`slow`].
+const SLOW_REPLY: Duration = Duration::from_millis(500);
+
+/// Connections whose next `GetManagedObjects` is answered after [`SLOW_REPLY`], with no objects.
+static SLOW: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// A method call received by the mock service.
+#[derive(Debug, Clone)]
+struct Call {
+    path: String,
+    member: String,
+    no_user_interaction: Option<bool>,
+}
+
+type Objects = HashMap<dbus::Path<'static>, HashMap<String, PropMap>>;
+
+fn prop(value: impl RefArg + 'static) -> Variant<Box<dyn RefArg>> {
+    Variant(Box::new(value))
+}
+
+fn path(path: &str) -> dbus::Path<'static> {
+    dbus::Path::new(path.to_owned()).unwrap()
+}
+
+fn device(device: &str) -> Vec<u8> {
+    let mut bytes = device.as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+}
+
+fn objects() -> Objects {
+    let mut drive = PropMap::new();
+    drive.insert("Id".into(), prop(String::from("Mock-Disk-1234")));
+    drive.insert("Model".into(), prop(String::from("Mock Disk")));
+    drive.insert("Serial".into(), prop(String::from("1234")));
+    drive.insert("Size".into(), prop(1u64 << 30));
+    drive.insert("CanPowerOff".into(), prop(true));
+
+    let mut disk = PropMap::new();
+    disk.insert("Device".into(), prop(device("/dev/sda")));
+    disk.insert("PreferredDevice".into(), prop(device("/dev/sda")));
+    disk.insert("CryptoBackingDevice".into(), prop(path("/")));
+    disk.insert("Drive".into(), prop(path(DRIVE)));
+    disk.insert("Size".into(), prop(1u64 << 30));
+    let mut table = PropMap::new();
+    table.insert("Type".into(), prop(String::from("gpt")));
+    table.insert("Partitions".into(), prop(vec![path(PARTITION)]));
+
+    let mut partition = PropMap::new();
+    partition.insert("Device".into(), prop(device("/dev/sda1")));
+    partition.insert("PreferredDevice".into(), prop(device("/dev/sda1")));
+    partition.insert("CryptoBackingDevice".into(), prop(path("/")));
+    partition.insert("Drive".into(), prop(path(DRIVE)));
+    partition.insert("Size".into(), prop(1u64 << 29));
+    partition.insert("IdType".into(), prop(String::from("ext4")));
+    partition.insert("IdUsage".into(), prop(String::from("filesystem")));
+    partition.insert("IdLabel".into(), prop(String::from("data")));
+    let mut entry = PropMap::new();
+    entry.insert("Table".into(), prop(path(DISK)));
+    entry.insert("Offset".into(), prop(1u64 << 20));
+    entry.insert("Size".into(), prop(1u64 << 29));
+    entry.insert("Number".into(), prop(1u32));
+    let mut filesystem = PropMap::new();
+    filesystem.insert("MountPoints".into(), prop(vec![device("/mnt/data")]));
+
+    // A block being removed while the objects are fetched, whose device is not a byte array.
+    let mut malformed = PropMap::new();
+    malformed.insert("Device".into(), prop(String::new()));
+    malformed.insert("PreferredDevice".into(), prop(String::new()));
+    malformed.insert("CryptoBackingDevice".into(), prop(path("/")));
+    malformed.insert("Drive".into(), prop(path("/")));
+    malformed.insert("Size".into(), prop(1u64 << 20));
+
+    // A device mapper block, whose device is a `dm-N` node rather than its name in `/dev/mapper`.
+    let mut mapper = PropMap::new();
+    mapper.insert("Device".into(), prop(device("/dev/dm-0")));
+    mapper.insert(
+        "PreferredDevice".into(),
+        prop(device("/dev/mapper/cryptroot")),
+    );
+    mapper.insert(
+        "Symlinks".into(),
+        prop(vec![
+            device("/dev/disk/by-id/dm-name-cryptroot"),
+            device("/dev/mapper/cryptroot"),
+        ]),
+    );
+    mapper.insert("CryptoBackingDevice".into(), prop(path("/")));
+    mapper.insert("Drive".into(), prop(path("/")));
+    mapper.insert("Size".into(), prop(1u64 << 28));
+
+    let mut objects = Objects::new();
+    objects.insert(
+        path(DRIVE),
+        vec![
+            ("org.freedesktop.UDisks2.Drive".into(), drive),
+            (
+                "org.freedesktop.UDisks2.NVMe.Controller".into(),
+                PropMap::new(),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    objects.insert(
+        path(DISK),
+        vec![
+            ("org.freedesktop.UDisks2.Block".into(), disk),
+            ("org.freedesktop.UDisks2.PartitionTable".into(), table),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    objects.insert(
+        path(PARTITION),
+        vec![
+            ("org.freedesktop.UDisks2.Block".into(), partition),
+            ("org.freedesktop.UDisks2.Partition".into(), entry),
+            ("org.freedesktop.UDisks2.Filesystem".into(), filesystem),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    objects.insert(
+        path(MALFORMED),
+        vec![("org.freedesktop.UDisks2.Block".into(), malformed)]
+            .into_iter()
+            .collect(),
+    );
+    objects.insert(
+        path(MAPPER),
+        vec![("org.freedesktop.UDisks2.Block".into(), mapper)]
+            .into_iter()
+            .collect(),
+    );
+    objects
+}
+
+fn reply(msg: &Message, calls: &Mutex<Vec<Call>>) -> Message {
+    let interface = msg.interface();
+    let member = msg.member();
+    match (interface.as_deref(), member.as_deref()) {
+        (Some("org.freedesktop.DBus.ObjectManager"), Some("GetManagedObjects")) => {
+            msg.method_return().append1(objects())
+        }
+        (Some("org.freedesktop.DBus.Properties"), Some("Get")) => match msg.read2::<&str, &str>() {
+            Ok(("org.freedesktop.UDisks2.Manager", "DefaultEncryptionType")) => {
+                msg.method_return().append1(prop(String::from("luks2")))
+            }
+            Ok(("org.freedesktop.UDisks2.Filesystem", "MountPoints")) => {
+                msg.method_return().append1(prop(vec![device(MOUNT_PATH)]))
+            }
+            Ok(("org.freedesktop.UDisks2.Drive", "MediaAvailable")) => {
+                msg.method_return().append1(prop(false))
+            }
+            Ok(("org.freedesktop.UDisks2.Manager", "SupportedFilesystems")) => msg
+                .method_return()
+                .append1(prop(vec![String::from("ext4"), String::from("xfs")])),
+            _ => unknown(msg),
+        },
+        // Like UDisks, each of these replies with a single struct.
+        (
+            Some("org.freedesktop.UDisks2.Manager"),
+            Some("CanFormat") | Some("CanCheck") | Some("CanRepair"),
+        ) => msg.method_return().append1((true, String::new())),
+        (Some("org.freedesktop.UDisks2.Manager"), Some("CanResize")) => {
+            // Offline and online growing for ext4, along with offline shrinking, but only online
+            // growing for xfs.
+            let mode: u64 = match msg.read1::<&str>() {
+                Ok("ext4") => (1 << 1) | (1 << 2) | (1 << 4),
+                _ => 1 << 4,
+            };
+            msg.method_return().append1((true, mode, String::new()))
+        }
+        (Some("org.freedesktop.UDisks2.Filesystem"), Some("Mount")) => {
+            record(msg, calls);
+            msg.method_return().append1(MOUNT_PATH)
+        }
+        (Some("org.freedesktop.UDisks2.Drive"), Some("PowerOff")) => {
+            record(msg, calls);
+            msg.method_return()
+        }
+        (Some("org.freedesktop.UDisks2.Block"), Some("Format")) => {
+            record(msg, calls);
+            msg.method_return()
+        }
+        (Some("org.freedesktop.UDisks2.Filesystem"), Some("Unmount")) => {
+            record(msg, calls);
+            msg.method_return()
+        }
+        _ => unknown(msg),
+    }
+}
+
+/// The `InterfacesRemoved` signal which UDisks emits once a drive is powered off, and so
+/// removed, if the message powers one off.
+fn removal(msg: &Message) -> Option<Message> {
+    if msg.member().as_deref() != Some("PowerOff") {
+        return None;
+    }
+    let removed = ObjectManagerInterfacesRemoved {
+        object: msg.path()?.into_static(),
+        interfaces: vec!["org.freedesktop.UDisks2.Drive".into()],
+    };
+    Some(removed.to_emit_message(&path("/org/freedesktop/UDisks2")))
+}
+
+fn record(msg: &Message, calls: &Mutex<Vec<Call>>) {
+    let options = msg.read1::<PropMap>().unwrap_or_default();
+    calls.lock().unwrap().push(Call {
+        path: msg.path().map(|p| p.to_string()).unwrap_or_default(),
+        member: msg.member().map(|m| m.to_string()).unwrap_or_default(),
+        no_user_interaction: options
+            .get("auth.no_user_interaction")
+            .and_then(|v| v.0.as_u64())
+            .map(|v| v != 0),
+    });
+}
+
+fn unknown(msg: &Message) -> Message {
+    let name = ErrorName::new("org.freedesktop.DBus.Error.UnknownMethod").unwrap();
+    msg.error(&name, &CString::new("not mocked").unwrap())
+}
+
+/// Have the next `GetManagedObjects` of the connection with the given unique name reply after
+/// [`SLOW_REPLY`], with no objects.
+#[cfg(feature = "futures")]
+fn slow(unique_name: &str) {
+    SLOW.lock().unwrap().push(unique_name.to_owned());
+}
+
+/// Take the connection which sent the message off the list of slow connections, returning
+/// whether it was on it.
+fn take_slow(msg: &Message) -> bool {
+    let sender = match msg.sender() {
+        Some(sender) => sender,
+        None => return false,
+    };
+    let mut slow = SLOW.lock().unwrap();
+    match slow.iter().position(|name| **name == *sender) {
+        Some(index) => {
+            slow.remove(index);
+            msg.member().as_deref() == Some("GetManagedObjects")
+        }
+        None => false,
+    }
+}
+
+/// Connect to the session bus as `org.freedesktop.UDisks2`, answering method calls with
+/// [`reply`], or after a delay for [`slow`] connections, which are queued in `delayed`.
+fn serve(
+    calls: Arc<Mutex<Vec<Call>>>,
+    delayed: Arc<Mutex<Vec<(Instant, Message)>>>,
+) -> Result<Connection, dbus::Error> {
+    let conn = Connection::new_session()?;
+    conn.request_name("org.freedesktop.UDisks2", false, true, true)?;
+    conn.start_receive(
+        MatchRule::new_method_call(),
+        Box::new(move |msg, conn| {
+            if take_slow(&msg) {
+                let reply = msg.method_return().append1(Objects::new());
+                let due = Instant::now() + SLOW_REPLY;
+                delayed.lock().unwrap().push((due, reply));
+            } else {
+                let _ = conn.send(reply(&msg, &calls));
+                if let Some(signal) = removal(&msg) {
+                    let _ = conn.send(signal);
+                }
+            }
+            true
+        }),
+    );
+    Ok(conn)
+}
+
+/// Start the mock service once, shared by every test, returning the calls it has received.
+///
+/// Panics with the reason if the service can't be started, such as without a session bus.
+fn mock() -> Arc<Mutex<Vec<Call>>> {
+    static CALLS: OnceLock<Arc<Mutex<Vec<Call>>>> = OnceLock::new();
+    CALLS
+        .get_or_init(|| {
+            let calls = Arc::new(Mutex::new(Vec::new()));
+            let service_calls = calls.clone();
+            let (ready, wait) = mpsc::channel();
+            std::thread::spawn(move || {
+                let delayed = Arc::new(Mutex::new(Vec::new()));
+                let conn = match serve(service_calls, delayed.clone()) {
+                    Ok(conn) => conn,
+                    Err(why) => {
+                        let _ = ready.send(Err(why.to_string()));
+                        return;
+                    }
+                };
+                let _ = ready.send(Ok(()));
+                loop {
+                    conn.process(Duration::from_millis(10)).unwrap();
+                    let now = Instant::now();
+                    let mut delayed = delayed.lock().unwrap();
+                    let (replies, pending) = delayed.drain(..).partition(|(due, _)| *due <= now);
+                    *delayed = pending;
+                    for (_, reply) in replies {
+                        let _ = conn.send(reply);
+                    }
+                }
+            });
+            match wait.recv() {
+                Ok(Ok(())) => calls,
+                Ok(Err(why)) => panic!(
+                    "failed to start the mock UDisks2 service on the session bus, which may be \
+                     started with `dbus-run-session`: {}",
+                    why
+                ),
+                Err(_) => panic!("the mock UDisks2 service stopped while starting"),
+            }
+        })
+        .clone()
+}
+
+fn udisks2() -> UDisks2 {
+    mock();
+    UDisks2Builder::default()
+        .connection(Connection::new_session().unwrap())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn parses_managed_objects() {
+    let udisks2 = udisks2();
+
+    let drive = udisks2.get_drive(DRIVE).unwrap();
+    assert_eq!(drive.display_name(), "Mock Disk");
+
+    let partition = udisks2.get_block_by_device(Path::new("/dev/sda1")).unwrap();
+    assert_eq!(partition.filesystem_type(), Some("ext4"));
+    assert_eq!(partition.id_label.as_deref(), Some("data"));
+    assert_eq!(udisks2.whole_disk_of(&partition).unwrap().path, DISK);
+}
+
+#[test]
+fn parses_block_with_malformed_device() {
+    let udisks2 = udisks2();
+
+    let block = udisks2.get_block(MALFORMED).unwrap();
+    assert_eq!(block.device, Path::new(""));
+    assert_eq!(block.size, 1 << 20);
+}
+
+#[test]
+fn finds_mapper_block_by_device() {
+    let udisks2 = udisks2();
+
+    for device in &["/dev/mapper/cryptroot", "/dev/dm-0"] {
+        let block = udisks2.get_block_by_device(Path::new(device)).unwrap();
+        assert_eq!(block.path, MAPPER);
+    }
+}
+
+#[test]
+fn reports_unhandled_interfaces() {
+    let udisks2 = udisks2();
+
+    let unhandled = udisks2.unhandled_interfaces();
+    assert_eq!(
+        unhandled.into_iter().collect::<Vec<_>>(),
+        vec!["org.freedesktop.UDisks2.NVMe.Controller"]
+    );
+}
+
+#[test]
+fn reads_filesystem_capabilities() {
+    let udisks2 = udisks2();
+
+    let capabilities = udisks2.filesystem_capabilities().unwrap();
+    assert_eq!(capabilities.len(), 2);
+
+    let ext4 = &capabilities["ext4"];
+    assert!(ext4.can_create && ext4.can_check && ext4.can_repair);
+    assert!(ext4.can_grow_offline && ext4.can_grow_online && ext4.can_shrink_offline);
+    assert!(!ext4.can_shrink_online);
+
+    let xfs = &capabilities["xfs"];
+    assert!(xfs.can_grow_online);
+    assert!(!xfs.can_grow_offline && !xfs.can_shrink_offline && !xfs.can_shrink_online);
+}
+
+#[test]
+fn unmount_calls_filesystem_unmount() {
+    let calls = mock();
+    let udisks2 = udisks2();
+
+    udisks2
+        .unmount(PARTITION, &UnmountOptions::default(), false, TIMEOUT)
+        .unwrap();
+
+    let calls = calls.lock().unwrap();
+    let call = calls
+        .iter()
+        .find(|call| call.path == PARTITION && call.member == "Unmount")
+        .unwrap();
+    assert_eq!(call.no_user_interaction, Some(true));
+}
+
+#[test]
+fn unmount_no_wait_sends_unmount() {
+    let calls = mock();
+    let udisks2 = udisks2();
+
+    udisks2
+        .unmount_no_wait(DISK, &UnmountOptions::default(), true)
+        .unwrap();
+
+    let sent = || {
+        calls
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|call| call.path == DISK && call.member == "Unmount")
+    };
+    let start = std::time::Instant::now();
+    while !sent() {
+        assert!(start.elapsed() < TIMEOUT, "Unmount was not received");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn mount_patches_mount_points() {
+    let mut udisks2 = udisks2();
+
+    let (mount_path, block) = udisks2
+        .mount(PARTITION, &MountOptions::default(), false, TIMEOUT)
+        .unwrap();
+    assert_eq!(mount_path, Path::new(MOUNT_PATH));
+    assert_eq!(block.mount_points, vec![Path::new(MOUNT_PATH)]);
+    assert_eq!(
+        udisks2.get_block(PARTITION).unwrap().mount_points,
+        vec![Path::new(MOUNT_PATH)]
+    );
+}
+
+#[test]
+fn format_returns_block() {
+    let calls = mock();
+    let udisks2 = udisks2();
+
+    let block = udisks2
+        .format(PARTITION, "ext4", &FormatOptions::default(), false, TIMEOUT)
+        .unwrap();
+    assert_eq!(block.path, PARTITION);
+    assert!(calls
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|call| call.path == PARTITION && call.member == "Format"));
+}
+
+#[test]
+fn reformat_partition_refuses_extended() {
+    let udisks2 = udisks2();
+    let mut partition = udisks2.get_block(PARTITION).unwrap();
+    partition.partition.as_mut().unwrap().is_container = true;
+
+    let result = udisks2.reformat_partition(
+        &partition,
+        "ext4",
+        &FormatOptions::default(),
+        false,
+        TIMEOUT,
+    );
+    assert!(matches!(result, Err(UdisksError::ExtendedPartition(_))));
+}
+
+#[test]
+fn reads_manager_properties() {
+    let udisks2 = udisks2();
+    assert_eq!(udisks2.default_encryption_type().unwrap(), "luks2");
+}
+
+#[cfg(feature = "futures")]
+#[tokio::test]
+async fn dropped_update_leaves_cache() {
+    mock();
+    let (resource, conn) = dbus_tokio::connection::new_session_sync().unwrap();
+    tokio::spawn(resource);
+    let mut udisks2 = dbus_udisks2::AsyncUDisks2::new(conn.clone()).await.unwrap();
+    let cached = udisks2
+        .get_blocks()
+        .map(|block| block.path)
+        .collect::<BTreeSet<_>>();
+    assert!(cached.contains(PARTITION));
+
+    // The slow reply has no objects, so the cache would be emptied if it were applied.
+    slow(&conn.unique_name());
+    let update = tokio::time::timeout(SLOW_REPLY / 5, udisks2.update()).await;
+    assert!(update.is_err(), "update completed before the slow reply");
+
+    // Let the reply arrive after the future was dropped.
+    tokio::time::delay_for(SLOW_REPLY * 2).await;
+    let blocks = udisks2.get_blocks().map(|block| block.path).collect();
+    assert_eq!(cached, blocks);
+
+    udisks2.update().await.unwrap();
+    assert!(udisks2.get_block(PARTITION).is_some());
+}
+
+#[cfg(feature = "futures")]
+#[tokio::test]
+async fn watch_block_mount_follows_signals() {
+    use dbus::blocking::stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged;
+    use futures_util::StreamExt;
+
+    mock();
+    let (resource, conn) = dbus_tokio::connection::new_session_sync().unwrap();
+    tokio::spawn(resource);
+    let udisks2 = dbus_udisks2::AsyncUDisks2::new(conn).await.unwrap();
+    let block = udisks2.get_block(PARTITION).unwrap();
+    let mut mount_points = udisks2.watch_block_mount(&block).await.unwrap();
+
+    let emitter = Connection::new_session().unwrap();
+    let mut changed_properties = PropMap::new();
+    changed_properties.insert("MountPoints".into(), prop(vec![device(MOUNT_PATH)]));
+    let changed = PropertiesPropertiesChanged {
+        interface_name: "org.freedesktop.UDisks2.Filesystem".into(),
+        changed_properties,
+        invalidated_properties: Vec::new(),
+    };
+    emitter
+        .send(changed.to_emit_message(&path(PARTITION)))
+        .unwrap();
+    let next = tokio::time::timeout(TIMEOUT, mount_points.next()).await;
+    assert_eq!(
+        next.unwrap(),
+        Some(vec![Path::new(MOUNT_PATH).to_path_buf()])
+    );
+
+    let removed = ObjectManagerInterfacesRemoved {
+        object: path(PARTITION),
+        interfaces: vec!["org.freedesktop.UDisks2.Block".into()],
+    };
+    emitter
+        .send(removed.to_emit_message(&path("/org/freedesktop/UDisks2")))
+        .unwrap();
+    let next = tokio::time::timeout(TIMEOUT, mount_points.next()).await;
+    assert_eq!(next.unwrap(), None);
+}
+
+#[cfg(feature = "futures")]
+#[tokio::test]
+async fn await_media_follows_signals() {
+    use dbus::blocking::stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged;
+
+    mock();
+    let (resource, conn) = dbus_tokio::connection::new_session_sync().unwrap();
+    tokio::spawn(resource);
+    let mut udisks2 = dbus_udisks2::AsyncUDisks2::new(conn).await.unwrap();
+
+    let result = udisks2.await_media(DRIVE, SLOW_REPLY / 5).await;
+    assert!(matches!(result, Err(UdisksError::TimedOut(_))));
+
+    let emitter = Connection::new_session().unwrap();
+    let mut changed_properties = PropMap::new();
+    changed_properties.insert("MediaAvailable".into(), prop(true));
+    let changed = PropertiesPropertiesChanged {
+        interface_name: "org.freedesktop.UDisks2.Drive".into(),
+        changed_properties,
+        invalidated_properties: Vec::new(),
+    };
+    let inserted = async {
+        tokio::time::delay_for(SLOW_REPLY / 5).await;
+        emitter.send(changed.to_emit_message(&path(DRIVE))).unwrap();
+    };
+    let (result, ()) = tokio::join!(udisks2.await_media(DRIVE, TIMEOUT), inserted);
+    result.unwrap();
+}
+
+#[cfg(feature = "futures")]
+#[tokio::test]
+async fn safe_remove_verified_awaits_removal() {
+    let calls = mock();
+    let (resource, conn) = dbus_tokio::connection::new_session_sync().unwrap();
+    tokio::spawn(resource);
+    let udisks2 = dbus_udisks2::AsyncUDisks2::new(conn).await.unwrap();
+    let drive = udisks2.get_drive(DRIVE).unwrap();
+
+    udisks2
+        .safe_remove_verified(&drive, false, TIMEOUT)
+        .await
+        .unwrap();
+    let calls = calls.lock().unwrap();
+    assert!(calls
+        .iter()
+        .any(|call| call.path == PARTITION && call.member == "Unmount"));
+    assert!(calls
+        .iter()
+        .any(|call| call.path == DRIVE && call.member == "PowerOff"));
+}
+
+#[cfg(feature = "futures")]
+#[tokio::test]
+async fn safe_remove_verified_times_out() {
+    mock();
+    let (resource, conn) = dbus_tokio::connection::new_session_sync().unwrap();
+    tokio::spawn(resource);
+    let udisks2 = dbus_udisks2::AsyncUDisks2::new(conn).await.unwrap();
+    // Without powering off or ejecting, the drive stays until it's unplugged.
+    let mut drive = udisks2.get_drive(DRIVE).unwrap();
+    drive.can_power_off = false;
+    drive.ejectable = false;
+
+    let result = udisks2
+        .safe_remove_verified(&drive, false, SLOW_REPLY / 5)
+        .await;
+    assert!(matches!(result, Err(UdisksError::TimedOut(_))));
+}